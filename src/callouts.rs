@@ -0,0 +1,374 @@
+use crate::text::PlainTextRenderer;
+use crate::{node_end_line, node_start_line};
+use markdown::{ParseOptions, mdast};
+use serde::{Deserialize, Serialize};
+
+/// The kind of a callout, from GitHub's five alert types.
+///
+/// Directive names map onto them where they mean the same thing: `info` is a
+/// note and `danger` a caution. Any other name is `Custom`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CalloutKind {
+    Note,
+    Tip,
+    Important,
+    Warning,
+    Caution,
+    Custom,
+}
+
+impl CalloutKind {
+    fn from_name(name: &str) -> Self {
+        match name.to_ascii_lowercase().as_str() {
+            "note" | "info" => CalloutKind::Note,
+            "tip" => CalloutKind::Tip,
+            "important" => CalloutKind::Important,
+            "warning" => CalloutKind::Warning,
+            "caution" | "danger" => CalloutKind::Caution,
+            _ => CalloutKind::Custom,
+        }
+    }
+}
+
+/// A GitHub alert (`> [!WARNING]`) or a directive container (`:::note`).
+#[derive(Debug, Clone)]
+pub struct Callout {
+    pub kind: CalloutKind,
+    /// The name as written, such as `WARNING` or `details`.
+    pub name: String,
+    /// Title of a directive, from `:::tip[Title]` or `:::tip Title`.
+    pub title: Option<String>,
+    /// Plain text of the callout's content, without its markers.
+    pub text: String,
+    pub start_line: Option<usize>,
+    pub end_line: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct JsonCallout {
+    pub kind: CalloutKind,
+    pub name: String,
+    pub title: Option<String>,
+    pub text: String,
+    pub start_line: Option<usize>,
+    pub end_line: Option<usize>,
+}
+
+impl From<Callout> for JsonCallout {
+    fn from(callout: Callout) -> Self {
+        JsonCallout {
+            kind: callout.kind,
+            name: callout.name,
+            title: callout.title,
+            text: callout.text,
+            start_line: callout.start_line,
+            end_line: callout.end_line,
+        }
+    }
+}
+
+const ALERT_NAMES: [&str; 5] = ["NOTE", "TIP", "IMPORTANT", "WARNING", "CAUTION"];
+
+/// The name in the `[!NAME]` marker opening a blockquote, if it is a GitHub
+/// alert. The marker has to be alone on the first line.
+pub(crate) fn alert_name(node: &mdast::Node) -> Option<&str> {
+    let mdast::Node::Blockquote(quote) = node else {
+        return None;
+    };
+    let Some(mdast::Node::Paragraph(paragraph)) = quote.children.first() else {
+        return None;
+    };
+    let Some(mdast::Node::Text(text)) = paragraph.children.first() else {
+        return None;
+    };
+
+    let first_line = text.value.split('\n').next().unwrap_or_default();
+    let name = first_line
+        .trim_end()
+        .strip_prefix("[!")?
+        .strip_suffix(']')?;
+    ALERT_NAMES
+        .iter()
+        .any(|alert| alert.eq_ignore_ascii_case(name))
+        .then_some(name)
+}
+
+/// The callout for a GitHub alert blockquote.
+pub(crate) fn alert(node: &mdast::Node, renderer: &PlainTextRenderer) -> Option<Callout> {
+    let name = alert_name(node)?;
+
+    Some(Callout {
+        kind: CalloutKind::from_name(name),
+        name: name.to_string(),
+        title: None,
+        // The renderer already drops the marker.
+        text: renderer.render(node),
+        start_line: node_start_line(node),
+        end_line: node_end_line(node),
+    })
+}
+
+/// Drop the lines of `text` that open or close a directive container.
+///
+/// `text` is rendered from source starting at `first_line`, one line per
+/// source line. Only lines listed in `directive_lines` are dropped, so prose
+/// that merely starts with `:::` is kept.
+pub(crate) fn strip_directive_markers(
+    text: String,
+    first_line: usize,
+    directive_lines: &[usize],
+) -> String {
+    let is_marker = |index: usize, line: &str| {
+        let line = line.trim();
+        directive_lines.contains(&(first_line + index))
+            && (closes_directive(line) || opens_directive(line).is_some())
+    };
+
+    if !text.lines().enumerate().any(|(i, line)| is_marker(i, line)) {
+        return text;
+    }
+    text.lines()
+        .enumerate()
+        .filter(|&(i, line)| !is_marker(i, line))
+        .map(|(_, line)| line)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn closes_directive(line: &str) -> bool {
+    line.len() >= 3 && line.chars().all(|c| c == ':')
+}
+
+/// Name and title of a `:::name[title]` or `:::name title` opening line.
+fn opens_directive(line: &str) -> Option<(&str, Option<String>)> {
+    let rest = line.trim_start_matches(':');
+    if line.len() - rest.len() < 3 {
+        return None;
+    }
+
+    let rest = rest.trim_start();
+    let name_end = rest
+        .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+        .unwrap_or(rest.len());
+    let (name, rest) = rest.split_at(name_end);
+    if !name.starts_with(|c: char| c.is_alphabetic()) {
+        return None;
+    }
+
+    let title = match rest.strip_prefix('[') {
+        Some(label) => label.split_once(']').map(|(title, _)| title.trim()),
+        // Drop a trailing `{attributes}` group.
+        None => Some(rest.split('{').next().unwrap_or_default().trim()),
+    };
+    Some((name, title.filter(|t| !t.is_empty()).map(str::to_string)))
+}
+
+/// A closed `:::name` ... `:::` container, by 0-based line index.
+struct Directive<'a> {
+    open: usize,
+    close: usize,
+    name: &'a str,
+    title: Option<String>,
+}
+
+/// Scan `lines` for directive containers. Markers inside fenced code are
+/// ignored, as are containers that are never closed.
+fn scan_directives<'a>(lines: &[&'a str]) -> Vec<Directive<'a>> {
+    let mut open: Vec<(usize, &str, Option<String>)> = Vec::new();
+    let mut fence: Option<&str> = None;
+    let mut directives = Vec::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            continue;
+        }
+        if trimmed.starts_with("```") {
+            fence = Some("```");
+        } else if trimmed.starts_with("~~~") {
+            fence = Some("~~~");
+        } else if closes_directive(trimmed) {
+            if let Some((start, name, title)) = open.pop() {
+                directives.push(Directive {
+                    open: start,
+                    close: index,
+                    name,
+                    title,
+                });
+            }
+        } else if let Some((name, title)) = opens_directive(trimmed) {
+            open.push((index, name, title));
+        }
+    }
+
+    directives.sort_by_key(|directive| directive.open);
+    directives
+}
+
+/// The 1-based lines of `src` that open or close a directive container, in
+/// ascending order.
+pub(crate) fn directive_lines(src: &str) -> Vec<usize> {
+    let lines: Vec<&str> = src.lines().collect();
+    let mut markers: Vec<usize> = scan_directives(&lines)
+        .iter()
+        .flat_map(|d| [d.open + 1, d.close + 1])
+        .collect();
+    markers.sort_unstable();
+    markers
+}
+
+/// Find the `:::name` ... `:::` directive containers in `src`, ordered by
+/// their opening line. Each one's content is parsed on its own and rendered
+/// with `renderer`. Markers inside fenced code are ignored, as are
+/// containers that are never closed.
+pub(crate) fn find_directives(
+    src: &str,
+    parse_options: &ParseOptions,
+    renderer: &PlainTextRenderer,
+) -> Vec<Callout> {
+    let lines: Vec<&str> = src.lines().collect();
+    let directives = scan_directives(&lines);
+
+    directives
+        .iter()
+        .map(|directive| {
+            let content = lines[directive.open + 1..directive.close].join("\n");
+            // Line 1 of the content is the line after the opening marker.
+            let nested: Vec<usize> = directives
+                .iter()
+                .filter(|d| d.open > directive.open && d.close < directive.close)
+                .flat_map(|d| [d.open - directive.open, d.close - directive.open])
+                .collect();
+            let text = match markdown::to_mdast(&content, parse_options) {
+                Ok(tree) => renderer.with_directive_lines(&nested).render(&tree),
+                Err(_) => content,
+            };
+
+            Callout {
+                kind: CalloutKind::from_name(directive.name),
+                name: directive.name.to_string(),
+                title: directive.title.clone(),
+                text,
+                start_line: Some(directive.open + 1),
+                end_line: Some(directive.close + 1),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{IndexOptions, index_markdown, index_markdown_with_options};
+
+    #[test]
+    fn directive_lines_are_recognized() {
+        assert_eq!(opens_directive(":::note"), Some(("note", None)));
+        assert_eq!(
+            opens_directive(":::tip[Pro tip]"),
+            Some(("tip", Some("Pro tip".to_string())))
+        );
+        assert_eq!(
+            opens_directive("::: warning Mind the gap {.wide}"),
+            Some(("warning", Some("Mind the gap".to_string())))
+        );
+        assert_eq!(opens_directive("::note"), None);
+        assert_eq!(opens_directive(":::"), None);
+        assert!(closes_directive(":::"));
+        assert!(closes_directive("::::"));
+    }
+
+    #[test]
+    fn github_alerts_become_callouts() {
+        let src = "# Setup\n\n> [!WARNING]\n> Back up *first*.\n\n> [!tip] not an alert\n\n> [!CUSTOM]\n> Neither.\n";
+
+        let sections = index_markdown(src).expect("parse ok");
+        let callouts = &sections[0].callouts;
+        assert_eq!(callouts.len(), 1);
+        assert_eq!(callouts[0].kind, CalloutKind::Warning);
+        assert_eq!(callouts[0].name, "WARNING");
+        assert_eq!(callouts[0].text, "Back up first.");
+        assert_eq!(
+            (callouts[0].start_line, callouts[0].end_line),
+            (Some(3), Some(4))
+        );
+
+        // The marker is left out of the section text as well.
+        assert_eq!(sections[0].body_text[0].text, "Back up first.");
+    }
+
+    #[test]
+    fn directive_containers_become_callouts() {
+        let src = "# Guide\n\n:::danger[Careful]\nDo not run this.\n\n```sh\n:::\n```\n:::\n\n:::details\nMore.\n:::\n\n:::note\nnever closed\n";
+        let options = IndexOptions::default();
+
+        let sections = index_markdown_with_options(src, &options).expect("parse ok");
+        let callouts = &sections[0].callouts;
+        assert_eq!(callouts.len(), 2, "{:?}", callouts);
+
+        assert_eq!(callouts[0].kind, CalloutKind::Caution);
+        assert_eq!(callouts[0].name, "danger");
+        assert_eq!(callouts[0].title.as_deref(), Some("Careful"));
+        assert_eq!(callouts[0].text, "Do not run this.");
+        assert_eq!(
+            (callouts[0].start_line, callouts[0].end_line),
+            (Some(3), Some(9))
+        );
+
+        assert_eq!(callouts[1].kind, CalloutKind::Custom);
+        assert_eq!(callouts[1].name, "details");
+        assert_eq!(callouts[1].text, "More.");
+
+        let texts: Vec<&str> = sections[0]
+            .body_text
+            .iter()
+            .map(|b| b.text.as_str())
+            .collect();
+        // A container that is never closed is not one, so its marker stays.
+        assert_eq!(
+            texts,
+            vec!["Do not run this.", "More.", ":::note\nnever closed"]
+        );
+    }
+
+    #[test]
+    fn only_real_directive_markers_are_dropped_from_text() {
+        let src = "# Syntax
+:::word starts this line.
+And this one does not.
+
+:::tip
+Outer.
+
+:::note
+Inner.
+:::
+:::
+";
+
+        let sections = index_markdown(src).expect("parse ok");
+        let texts: Vec<&str> = sections[0]
+            .body_text
+            .iter()
+            .map(|b| b.text.as_str())
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                ":::word starts this line.\nAnd this one does not.",
+                "Outer.",
+                "Inner."
+            ]
+        );
+
+        let callouts = &sections[0].callouts;
+        assert_eq!(callouts.len(), 2);
+        assert_eq!(callouts[0].text, "Outer.\nInner.");
+        assert_eq!(callouts[1].text, "Inner.");
+    }
+}
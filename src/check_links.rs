@@ -0,0 +1,429 @@
+use crate::is_markdown_file;
+use markdown::message::{Message, Place};
+use markdown2json::{Document, IndexOptions, Link, LinkKind, Section, index_document};
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+/// A broken link or a file that could not be checked, reported as
+/// `file:line: message`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub file: PathBuf,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// A markdown parse error in `file`, at the point it was found.
+    pub fn parse_error(file: &Path, message: &Message) -> Self {
+        let point = match message.place.as_deref() {
+            Some(Place::Point(point)) => Some(point),
+            Some(Place::Position(position)) => Some(&position.start),
+            None => None,
+        };
+
+        Diagnostic {
+            file: file.to_path_buf(),
+            line: point.map(|p| p.line),
+            column: point.map(|p| p.column),
+            message: message.reason.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:", self.file.display())?;
+        if let Some(line) = self.line {
+            write!(f, "{}:", line)?;
+            if let Some(column) = self.column {
+                write!(f, "{}:", column)?;
+            }
+        }
+        write!(f, " {}", self.message)
+    }
+}
+
+/// Result of checking a set of files.
+#[derive(Debug, Default)]
+pub struct Report {
+    pub links_checked: usize,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Check every internal and anchor link in `files`.
+///
+/// - `#anchor` links must match a heading anchor of the same file.
+/// - Relative links to markdown files must point to one of `files`, so a
+///   link into an ignored or excluded file is reported even though the file
+///   exists. With a `#fragment`, the fragment must match a heading anchor of
+///   the target. Other relative links only need an existing file or
+///   directory on disk.
+/// - Root-relative links (`/docs/a.md`) cannot be resolved without knowing
+///   the site root and are skipped, as are external URLs.
+///
+/// Links in footnote definitions are checked once each, referenced or not.
+///
+/// Files that cannot be read or parsed are reported too, and their links are
+/// not checked.
+pub fn check_links(files: &[PathBuf], options: &IndexOptions) -> Report {
+    let indexed = IndexedFiles::new(files, options);
+    let mut report = Report::default();
+
+    for (file, doc) in files.iter().zip(&indexed.documents) {
+        let doc = match doc {
+            Ok(doc) => doc,
+            Err(diagnostic) => {
+                report.diagnostics.push(diagnostic.clone());
+                continue;
+            }
+        };
+
+        let section_links = doc.sections.iter().flat_map(|s| &s.links);
+        let footnote_links = doc.footnotes.iter().flat_map(|f| &f.links);
+        for link in section_links.chain(footnote_links) {
+            if let Some(problem) = check_link(file, link, &doc.sections, &indexed) {
+                report.diagnostics.push(Diagnostic {
+                    file: file.clone(),
+                    line: link.line,
+                    column: None,
+                    message: format!("broken link `{}`: {}", link.destination, problem),
+                });
+            }
+            if link.kind != LinkKind::External {
+                report.links_checked += 1;
+            }
+        }
+    }
+
+    report
+}
+
+/// What is wrong with `link`, if anything.
+fn check_link(
+    file: &Path,
+    link: &Link,
+    own_sections: &[Section],
+    indexed: &IndexedFiles,
+) -> Option<String> {
+    match link.kind {
+        LinkKind::External => None,
+        LinkKind::Anchor => {
+            let fragment = percent_decode(&link.destination[1..]);
+            (!fragment.is_empty() && !has_anchor(own_sections, &fragment))
+                .then(|| format!("no heading matches anchor `#{}`", fragment))
+        }
+        LinkKind::Internal => check_internal_link(file, &link.destination, own_sections, indexed),
+    }
+}
+
+fn check_internal_link(
+    file: &Path,
+    destination: &str,
+    own_sections: &[Section],
+    indexed: &IndexedFiles,
+) -> Option<String> {
+    let (path_part, fragment) = match destination.split_once('#') {
+        Some((path, fragment)) => (path, Some(percent_decode(fragment))),
+        None => (destination, None),
+    };
+    let path_part = path_part.split('?').next().unwrap_or_default();
+
+    if path_part.starts_with('/') {
+        return None;
+    }
+
+    if path_part.is_empty() {
+        return fragment
+            .filter(|f| !f.is_empty() && !has_anchor(own_sections, f))
+            .map(|f| format!("no heading matches anchor `#{}`", f));
+    }
+
+    let target = file
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .join(percent_decode(path_part));
+
+    if !target.exists() {
+        return Some(format!("{} does not exist", target.display()));
+    }
+    if !target.is_file() || !is_markdown_file(&target, indexed.options) {
+        return None;
+    }
+
+    let sections = match indexed.get(&target) {
+        Some(Ok(doc)) => &doc.sections,
+        // Already reported as a problem of the target itself.
+        Some(Err(_)) => return None,
+        None => {
+            return Some(format!(
+                "{} is not among the checked files (ignored or excluded)",
+                target.display()
+            ));
+        }
+    };
+
+    match fragment {
+        Some(fragment) if !fragment.is_empty() => (!has_anchor(sections, &fragment)).then(|| {
+            format!(
+                "no heading in {} matches anchor `#{}`",
+                target.display(),
+                fragment
+            )
+        }),
+        _ => None,
+    }
+}
+
+fn has_anchor(sections: &[Section], fragment: &str) -> bool {
+    sections
+        .iter()
+        .filter_map(|s| s.anchor.as_deref())
+        .any(|anchor| anchor == fragment || anchor == fragment.to_lowercase())
+}
+
+/// Every checked file indexed, in the order of `files`, or the problem that
+/// kept it from being indexed.
+struct IndexedFiles<'a> {
+    options: &'a IndexOptions,
+    documents: Vec<Result<Document, Diagnostic>>,
+    /// Position in `documents`, keyed by canonical path.
+    by_path: HashMap<PathBuf, usize>,
+}
+
+impl<'a> IndexedFiles<'a> {
+    fn new(files: &[PathBuf], options: &'a IndexOptions) -> Self {
+        let documents = files.iter().map(|file| index_file(file, options)).collect();
+        let by_path = files
+            .iter()
+            .enumerate()
+            .map(|(i, file)| (canonical(file), i))
+            .collect();
+
+        IndexedFiles {
+            options,
+            documents,
+            by_path,
+        }
+    }
+
+    /// The document at `path`, or `None` if it is not one of the files.
+    fn get(&self, path: &Path) -> Option<&Result<Document, Diagnostic>> {
+        self.by_path
+            .get(&canonical(path))
+            .map(|&i| &self.documents[i])
+    }
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn index_file(file: &Path, options: &IndexOptions) -> Result<Document, Diagnostic> {
+    let src = fs::read_to_string(file).map_err(|err| Diagnostic {
+        file: file.to_path_buf(),
+        line: None,
+        column: None,
+        message: format!("cannot read file: {}", err),
+    })?;
+    index_document(&src, options).map_err(|message| Diagnostic::parse_error(file, &message))
+}
+
+/// Decode `%XX` escapes; invalid escapes are kept as-is.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(hex) = s.get(i + 1..i + 3)
+            && let Ok(byte) = u8::from_str_radix(hex, 16)
+        {
+            out.push(byte);
+            i += 3;
+            continue;
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_links, percent_decode};
+    use markdown2json::IndexOptions;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn unique_temp_dir() -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system time should be after epoch")
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!(
+            "markdown2json-links-{}-{}",
+            std::process::id(),
+            nanos
+        ));
+        fs::create_dir_all(&dir).expect("create temp dir");
+        dir
+    }
+
+    #[test]
+    fn decodes_percent_escapes() {
+        assert_eq!(percent_decode("my%20file.md"), "my file.md");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("caf%C3%A9"), "café");
+    }
+
+    #[test]
+    fn reports_missing_files_and_anchors_with_lines() {
+        let dir = unique_temp_dir();
+        let index = dir.join("index.md");
+        let guide = dir.join("guide.md");
+        fs::write(
+            &index,
+            "# Home\n\
+             [ok](guide.md) [ok](guide.md#getting-started) [ok](#home)\n\
+             [gone](missing.md)\n\
+             [renamed](guide.md#old-name) [dup](guide.md#intro-1) [custom](guide.md#old-heading)\n\
+             [self](#nowhere) [web](https://example.com)\n",
+        )
+        .expect("write index");
+        fs::write(
+            &guide,
+            "# Getting Started\n## Intro\n## Intro\n## Old {#old-heading}\n",
+        )
+        .expect("write guide");
+
+        let report = check_links(&[index.clone(), guide], &IndexOptions::default());
+
+        assert_eq!(report.links_checked, 8);
+        let messages: Vec<String> = report.diagnostics.iter().map(|d| d.to_string()).collect();
+        assert_eq!(messages.len(), 3, "{:?}", messages);
+        assert!(
+            messages[0].starts_with(&format!("{}:3: broken link `missing.md`", index.display()))
+        );
+        assert!(messages[1].starts_with(&format!("{}:4:", index.display())));
+        assert!(messages[1].contains("#old-name"));
+        assert!(messages[2].starts_with(&format!("{}:5:", index.display())));
+        assert!(messages[2].contains("#nowhere"));
+
+        fs::remove_dir_all(dir).expect("cleanup temp dir");
+    }
+
+    #[test]
+    fn files_that_do_not_parse_are_reported_with_their_position() {
+        let dir = unique_temp_dir();
+        let bad = dir.join("bad.mdx");
+        let good = dir.join("good.md");
+        fs::write(&bad, "# Bad\n\nText {oops\n").expect("write bad");
+        fs::write(&good, "[gone](missing.md)\n").expect("write good");
+        let options = IndexOptions {
+            mdx: true,
+            ..IndexOptions::default()
+        };
+
+        let report = check_links(&[bad.clone(), good.clone()], &options);
+        let messages: Vec<String> = report.diagnostics.iter().map(|d| d.to_string()).collect();
+        assert_eq!(messages.len(), 2, "{:?}", messages);
+        assert!(
+            messages[0].starts_with(&format!("{}:3:11: Unexpected end of file", bad.display())),
+            "{}",
+            messages[0]
+        );
+        assert!(messages[1].starts_with(&format!("{}:1: broken link", good.display())));
+
+        fs::remove_dir_all(dir).expect("cleanup temp dir");
+    }
+
+    #[test]
+    fn targets_must_be_checked_files_and_unreadable_ones_are_reported() {
+        let dir = unique_temp_dir();
+        let index = dir.join("index.md");
+        let binary = dir.join("bin.md");
+        fs::write(
+            &index,
+            "[skipped](vendor.md)\n[binary](bin.md#top)\n[gone](other.md)\n",
+        )
+        .expect("write index");
+        fs::write(dir.join("vendor.md"), "# Vendor\n").expect("write vendor");
+        fs::write(&binary, [0xff, 0xfe, b'#']).expect("write binary");
+
+        let report = check_links(&[index.clone(), binary.clone()], &IndexOptions::default());
+        let messages: Vec<String> = report.diagnostics.iter().map(|d| d.to_string()).collect();
+        assert_eq!(messages.len(), 3, "{:?}", messages);
+        assert!(
+            messages[0].starts_with(&format!("{}:1: broken link `vendor.md`", index.display()))
+        );
+        assert!(messages[0].contains("not among the checked files"));
+        assert!(messages[1].starts_with(&format!("{}:3: broken link `other.md`", index.display())));
+        assert!(
+            messages[2].starts_with(&format!("{}: cannot read file", binary.display())),
+            "{}",
+            messages[2]
+        );
+
+        fs::remove_dir_all(dir).expect("cleanup temp dir");
+    }
+
+    #[test]
+    fn footnote_links_are_checked_once_whether_or_not_referenced() {
+        let dir = unique_temp_dir();
+        let index = dir.join("index.md");
+        fs::write(
+            &index,
+            "# One
+See[^s].
+
+# Two
+Again[^s].
+
+[^s]: [shared](missing.md)
+[^u]: [unref](gone.md)
+",
+        )
+        .expect("write index");
+        let options = IndexOptions {
+            gfm: true,
+            ..IndexOptions::default()
+        };
+
+        let report = check_links(std::slice::from_ref(&index), &options);
+        assert_eq!(report.links_checked, 2);
+        let messages: Vec<String> = report.diagnostics.iter().map(|d| d.to_string()).collect();
+        assert_eq!(messages.len(), 2, "{:?}", messages);
+        assert!(
+            messages[0].starts_with(&format!("{}:7: broken link `missing.md`", index.display()))
+        );
+        assert!(messages[1].starts_with(&format!("{}:8: broken link `gone.md`", index.display())));
+
+        fs::remove_dir_all(dir).expect("cleanup temp dir");
+    }
+
+    #[test]
+    fn links_to_non_markdown_files_and_directories_only_need_to_exist() {
+        let dir = unique_temp_dir();
+        let index = dir.join("index.md");
+        fs::create_dir_all(dir.join("img")).expect("create img dir");
+        fs::write(dir.join("img/logo.png"), "").expect("write image");
+        fs::write(
+            &index,
+            "[logo](img/logo.png) [folder](img) [root](/abs/path.md)\n",
+        )
+        .expect("write index");
+
+        let report = check_links(&[index], &IndexOptions::default());
+        assert!(report.diagnostics.is_empty(), "{:?}", report.diagnostics);
+
+        fs::remove_dir_all(dir).expect("cleanup temp dir");
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+
+/// Parse the meta string of a fenced code block (everything after the
+/// language on the opening fence) into attributes.
+///
+/// - `key=value`, `key="quoted value"` and `key='quoted value'` pairs map
+///   `key` to the value.
+/// - Bare words such as `showLineNumbers` map to `"true"`.
+/// - A `{...}` group of line numbers and ranges (`{1,3-5}`) maps `highlight`
+///   to the ranges; any other `{...}` group is parsed like the rest, with
+///   `#id` and `.class` shorthands (classes are joined with spaces).
+///
+/// ```
+/// let attrs = markdown2json::parse_code_meta(r#"title="main.rs" {3-5} showLineNumbers"#);
+/// assert_eq!(attrs["title"], "main.rs");
+/// assert_eq!(attrs["highlight"], "3-5");
+/// assert_eq!(attrs["showLineNumbers"], "true");
+/// ```
+pub fn parse_code_meta(meta: &str) -> BTreeMap<String, String> {
+    let mut attributes = BTreeMap::new();
+    parse_into(meta, &mut attributes);
+    attributes
+}
+
+fn parse_into(meta: &str, attributes: &mut BTreeMap<String, String>) {
+    let mut rest = meta.trim_start();
+
+    while !rest.is_empty() {
+        if let Some(group) = rest.strip_prefix('{') {
+            let (inner, after) = group.split_once('}').unwrap_or((group, ""));
+            if is_line_ranges(inner) {
+                let ranges: String = inner.chars().filter(|c| !c.is_whitespace()).collect();
+                attributes.insert("highlight".to_string(), ranges);
+            } else {
+                parse_into(inner, attributes);
+            }
+            rest = after;
+        } else {
+            let end = rest
+                .find(|c: char| c.is_whitespace() || c == '=' || c == '{')
+                .unwrap_or(rest.len());
+            let key = &rest[..end];
+            rest = &rest[end..];
+
+            if let Some(after_eq) = rest.strip_prefix('=') {
+                let (value, after) = take_value(after_eq);
+                attributes.insert(key.to_string(), value.to_string());
+                rest = after;
+            } else if let Some(id) = key.strip_prefix('#') {
+                attributes.insert("id".to_string(), id.to_string());
+            } else if let Some(class) = key.strip_prefix('.') {
+                attributes
+                    .entry("class".to_string())
+                    .and_modify(|classes| {
+                        classes.push(' ');
+                        classes.push_str(class);
+                    })
+                    .or_insert_with(|| class.to_string());
+            } else if !key.is_empty() {
+                attributes.insert(key.to_string(), "true".to_string());
+            }
+        }
+
+        rest = rest.trim_start();
+    }
+}
+
+/// Split a possibly quoted value off the front of `s`.
+fn take_value(s: &str) -> (&str, &str) {
+    for quote in ['"', '\''] {
+        if let Some(quoted) = s.strip_prefix(quote) {
+            return match quoted.find(quote) {
+                Some(end) => (&quoted[..end], &quoted[end + 1..]),
+                None => (quoted, ""),
+            };
+        }
+    }
+
+    let end = s.find(char::is_whitespace).unwrap_or(s.len());
+    (&s[..end], &s[end..])
+}
+
+fn is_line_ranges(s: &str) -> bool {
+    s.chars().any(|c| c.is_ascii_digit())
+        && s.chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, ',' | '-') || c.is_whitespace())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_code_meta;
+
+    #[test]
+    fn parses_pairs_flags_and_line_ranges() {
+        let attrs = parse_code_meta("title='a b.rs' lines=10 {1, 3-5} copy");
+
+        assert_eq!(attrs.len(), 4);
+        assert_eq!(attrs["title"], "a b.rs");
+        assert_eq!(attrs["lines"], "10");
+        assert_eq!(attrs["highlight"], "1,3-5");
+        assert_eq!(attrs["copy"], "true");
+    }
+
+    #[test]
+    fn parses_attribute_groups() {
+        let attrs = parse_code_meta(r#"{#setup .numbered .wide title="install"}"#);
+
+        assert_eq!(attrs["id"], "setup");
+        assert_eq!(attrs["class"], "numbered wide");
+        assert_eq!(attrs["title"], "install");
+    }
+
+    #[test]
+    fn empty_meta_has_no_attributes() {
+        assert!(parse_code_meta("").is_empty());
+        assert!(parse_code_meta("   ").is_empty());
+    }
+}
@@ -0,0 +1,187 @@
+use crate::links::{self, Definitions, Image, JsonLink, Link};
+use crate::text::PlainTextRenderer;
+use crate::{CodeBlock, InlineCode, Math, node_end_line, node_start_line};
+use markdown::mdast;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A GFM footnote, attached to every section that references it.
+#[derive(Debug, Clone)]
+pub struct Footnote {
+    /// The label as written in the reference, `1` for `[^1]`.
+    pub label: String,
+    /// Plain text of the definition.
+    pub text: String,
+    /// Lines of the definition, which usually sit at the end of the file.
+    pub start_line: Option<usize>,
+    pub end_line: Option<usize>,
+    /// Links in the definition. They are kept here rather than in the links
+    /// of every referencing section, so each one is listed once.
+    pub links: Vec<Link>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct JsonFootnote {
+    pub label: String,
+    pub text: String,
+    pub start_line: Option<usize>,
+    pub end_line: Option<usize>,
+    pub links: Vec<JsonLink>,
+}
+
+impl From<Footnote> for JsonFootnote {
+    fn from(footnote: Footnote) -> Self {
+        JsonFootnote {
+            label: footnote.label,
+            text: footnote.text,
+            start_line: footnote.start_line,
+            end_line: footnote.end_line,
+            links: footnote.links.into_iter().map(JsonLink::from).collect(),
+        }
+    }
+}
+
+/// A footnote definition with the content that is indexed in the sections
+/// referencing it, since the section it is written in is usually unrelated.
+pub(crate) struct FootnoteDefinition {
+    pub(crate) footnote: Footnote,
+    pub(crate) code_blocks: Vec<CodeBlock>,
+    pub(crate) inline_code: Vec<InlineCode>,
+    pub(crate) math_blocks: Vec<Math>,
+    pub(crate) inline_math: Vec<Math>,
+    pub(crate) images: Vec<Image>,
+}
+
+/// Footnote definitions, keyed by identifier.
+pub(crate) type FootnoteDefinitions = HashMap<String, FootnoteDefinition>;
+
+/// Gather every footnote definition in the document, wherever it is nested.
+pub(crate) fn collect_definitions(
+    src: &str,
+    node: &mdast::Node,
+    renderer: &PlainTextRenderer,
+    definitions: &Definitions,
+    out: &mut FootnoteDefinitions,
+) {
+    if let mdast::Node::FootnoteDefinition(def) = node {
+        // The first definition of an identifier wins, as for links.
+        out.entry(def.identifier.clone()).or_insert_with(|| {
+            let mut definition = FootnoteDefinition {
+                footnote: Footnote {
+                    label: def.label.clone().unwrap_or_else(|| def.identifier.clone()),
+                    text: renderer.render(node),
+                    start_line: node_start_line(node),
+                    end_line: node_end_line(node),
+                    links: Vec::new(),
+                },
+                code_blocks: Vec::new(),
+                inline_code: Vec::new(),
+                math_blocks: Vec::new(),
+                inline_math: Vec::new(),
+                images: Vec::new(),
+            };
+            crate::collect_code_blocks(src, node, &mut definition.code_blocks);
+            crate::collect_inline_code(node, &mut definition.inline_code);
+            crate::collect_math(
+                node,
+                &mut definition.math_blocks,
+                &mut definition.inline_math,
+            );
+            links::collect_links(node, definitions, &mut definition.footnote.links);
+            links::collect_images(node, definitions, &mut definition.images);
+            definition
+        });
+    }
+
+    if let Some(children) = node.children() {
+        for child in children {
+            collect_definitions(src, child, renderer, definitions, out);
+        }
+    }
+}
+
+/// Push the definition for every footnote referenced in `node`, once each.
+/// References without a definition are skipped.
+pub(crate) fn collect_references<'a>(
+    node: &mdast::Node,
+    definitions: &'a FootnoteDefinitions,
+    out: &mut Vec<&'a FootnoteDefinition>,
+) {
+    match node {
+        mdast::Node::FootnoteReference(reference) => {
+            if let Some(definition) = definitions.get(&reference.identifier)
+                && !out
+                    .iter()
+                    .any(|d| d.footnote.label == definition.footnote.label)
+            {
+                out.push(definition);
+            }
+        }
+        // Footnotes referenced from other footnotes are not section content.
+        mdast::Node::FootnoteDefinition(_) => {}
+        _ => {
+            if let Some(children) = node.children() {
+                for child in children {
+                    collect_references(child, definitions, out);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{IndexOptions, index_document};
+
+    #[test]
+    fn footnotes_attach_to_the_sections_that_reference_them() {
+        let src = "# One\nFirst[^note] and again[^note].\n\n# Two\nSecond[^Big].\n\n# Three\nNo references.\n\n[^note]: A *shared* note, see [docs](docs.md).\n[^Big]: Its own `note`.\n\n    ```sh\n    cargo run\n    ```\n[^unused]: Never referenced.\n";
+        let options = IndexOptions {
+            gfm: true,
+            ..IndexOptions::default()
+        };
+
+        let doc = index_document(src, &options).expect("parse ok");
+        let sections = &doc.sections;
+        assert_eq!(sections.len(), 3);
+
+        let one = &sections[0].footnotes;
+        assert_eq!(one.len(), 1);
+        assert_eq!(one[0].label, "note");
+        assert_eq!(one[0].text, "A shared note, see docs.");
+        assert_eq!(one[0].start_line, Some(10));
+        // Links stay with the footnote, listed once however often it is
+        // referenced.
+        assert_eq!(one[0].links.len(), 1);
+        assert_eq!(one[0].links[0].destination, "docs.md");
+        assert!(sections[0].links.is_empty());
+
+        let two = &sections[1].footnotes;
+        assert_eq!(two.len(), 1);
+        assert_eq!(two[0].label, "Big");
+        assert_eq!(two[0].text, "Its own note.");
+
+        // Code in a definition is indexed where the footnote is referenced.
+        let code: Vec<&str> = sections[1]
+            .code_blocks
+            .iter()
+            .map(|c| c.value.as_str())
+            .collect();
+        assert_eq!(code, vec!["cargo run"]);
+        assert_eq!(sections[1].inline_code[0].value, "note");
+
+        // The section the definitions are written in gets none of their
+        // content, links included.
+        let three = &sections[2];
+        assert!(three.footnotes.is_empty());
+        assert_eq!(three.body_text.len(), 1);
+        assert!(three.code_blocks.is_empty());
+        assert!(three.inline_code.is_empty());
+        assert!(three.links.is_empty());
+        assert_eq!(three.end_line, Some(8));
+
+        // The document lists every definition, referenced or not.
+        let labels: Vec<&str> = doc.footnotes.iter().map(|f| f.label.as_str()).collect();
+        assert_eq!(labels, vec!["note", "Big", "unused"]);
+    }
+}
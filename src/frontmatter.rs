@@ -0,0 +1,73 @@
+use serde_json::{Map, Number, Value};
+use yaml_rust2::{Yaml, YamlLoader};
+
+/// Parse YAML frontmatter into JSON. Returns `None` for empty or invalid YAML.
+///
+/// Only the first document is used if the frontmatter holds several.
+pub(crate) fn yaml_to_json(src: &str) -> Option<Value> {
+    let yaml = YamlLoader::load_from_str(src).ok()?.into_iter().next()?;
+    match yaml_value_to_json(yaml) {
+        Value::Null => None,
+        value => Some(value),
+    }
+}
+
+fn yaml_value_to_json(value: Yaml) -> Value {
+    match value {
+        Yaml::String(s) => Value::String(s),
+        Yaml::Integer(i) => Value::Number(i.into()),
+        Yaml::Real(_) => value
+            .as_f64()
+            .and_then(Number::from_f64)
+            .map_or(Value::Null, Value::Number),
+        Yaml::Boolean(b) => Value::Bool(b),
+        Yaml::Array(items) => Value::Array(items.into_iter().map(yaml_value_to_json).collect()),
+        Yaml::Hash(hash) => Value::Object(
+            hash.into_iter()
+                .filter_map(|(key, value)| Some((yaml_key(key)?, yaml_value_to_json(value))))
+                .collect::<Map<String, Value>>(),
+        ),
+        Yaml::Alias(_) | Yaml::Null | Yaml::BadValue => Value::Null,
+    }
+}
+
+/// JSON keys are strings, so scalar keys are written out and the rest dropped.
+fn yaml_key(key: Yaml) -> Option<String> {
+    match key {
+        Yaml::String(s) | Yaml::Real(s) => Some(s),
+        Yaml::Integer(i) => Some(i.to_string()),
+        Yaml::Boolean(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Parse TOML frontmatter into JSON. Returns `None` for invalid TOML.
+///
+/// Datetimes become their RFC 3339 string, like YAML dates do.
+pub(crate) fn toml_to_json(src: &str) -> Option<Value> {
+    let table: toml::Table = toml::from_str(src).ok()?;
+    Some(toml_table_to_json(table))
+}
+
+fn toml_table_to_json(table: toml::Table) -> Value {
+    Value::Object(
+        table
+            .into_iter()
+            .map(|(key, value)| (key, toml_value_to_json(value)))
+            .collect::<Map<String, Value>>(),
+    )
+}
+
+fn toml_value_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::Number(i.into()),
+        toml::Value::Float(f) => Number::from_f64(f).map_or(Value::Null, Value::Number),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(d) => Value::String(d.to_string()),
+        toml::Value::Array(items) => {
+            Value::Array(items.into_iter().map(toml_value_to_json).collect())
+        }
+        toml::Value::Table(table) => toml_table_to_json(table),
+    }
+}
@@ -0,0 +1,439 @@
+use crate::{node_end_line, node_start_line};
+use markdown::mdast;
+use serde::{Deserialize, Serialize};
+
+/// A `<details>` element: a collapsible sub-section of the page.
+#[derive(Debug, Clone)]
+pub struct Details {
+    /// Text of the `<summary>`, empty if there is none.
+    pub summary: String,
+    /// Plain text of everything else inside the element, markdown included.
+    pub text: String,
+    pub start_line: Option<usize>,
+    pub end_line: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct JsonDetails {
+    pub summary: String,
+    pub text: String,
+    pub start_line: Option<usize>,
+    pub end_line: Option<usize>,
+}
+
+impl From<Details> for JsonDetails {
+    fn from(details: Details) -> Self {
+        JsonDetails {
+            summary: details.summary,
+            text: details.text,
+            start_line: details.start_line,
+            end_line: details.end_line,
+        }
+    }
+}
+
+/// An HTML block exactly as written.
+#[derive(Debug, Clone)]
+pub struct RawHtml {
+    pub value: String,
+    pub start_line: Option<usize>,
+    pub end_line: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct JsonRawHtml {
+    pub value: String,
+    pub start_line: Option<usize>,
+    pub end_line: Option<usize>,
+}
+
+impl From<RawHtml> for JsonRawHtml {
+    fn from(html: RawHtml) -> Self {
+        JsonRawHtml {
+            value: html.value,
+            start_line: html.start_line,
+            end_line: html.end_line,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+    /// An opening or closing tag, with its lowercased name.
+    Tag {
+        name: String,
+        closing: bool,
+    },
+    Text(&'a str),
+}
+
+/// Split `html` into tags and text. Comments, doctypes and the contents of
+/// `<script>` and `<style>` are dropped; a `<` that does not start a tag is
+/// kept as text.
+fn tokenize(html: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = html;
+
+    while let Some(open) = rest.find('<') {
+        if open > 0 {
+            tokens.push(Token::Text(&rest[..open]));
+        }
+        rest = &rest[open..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment
+                .split_once("-->")
+                .map(|(_, after)| after)
+                .unwrap_or("");
+            continue;
+        }
+        if rest.starts_with("<!") || rest.starts_with("<?") {
+            rest = rest.split_once('>').map(|(_, after)| after).unwrap_or("");
+            continue;
+        }
+
+        let (closing, after) = match rest[1..].strip_prefix('/') {
+            Some(after) => (true, after),
+            None => (false, &rest[1..]),
+        };
+        let name_end = after
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '-')
+            .unwrap_or(after.len());
+        if name_end == 0 || !after.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            tokens.push(Token::Text("<"));
+            rest = &rest[1..];
+            continue;
+        }
+
+        let name = after[..name_end].to_ascii_lowercase();
+        rest = skip_tag(&after[name_end..]);
+
+        if !closing && (name == "script" || name == "style") {
+            let end = format!("</{}", name);
+            rest = rest
+                .to_ascii_lowercase()
+                .find(&end)
+                .map(|i| skip_tag(&rest[i + end.len()..]))
+                .unwrap_or("");
+            continue;
+        }
+        tokens.push(Token::Tag { name, closing });
+    }
+
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest));
+    }
+    tokens
+}
+
+/// Skip past the `>` ending a tag, ignoring any inside quoted attributes.
+fn skip_tag(s: &str) -> &str {
+    let mut quote = None;
+    for (i, c) in s.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, '>') => return &s[i + 1..],
+            _ => {}
+        }
+    }
+    ""
+}
+
+/// Tags that start a new line in the text.
+fn is_block_tag(name: &str) -> bool {
+    matches!(
+        name,
+        "address"
+            | "article"
+            | "aside"
+            | "blockquote"
+            | "br"
+            | "dd"
+            | "details"
+            | "div"
+            | "dl"
+            | "dt"
+            | "figcaption"
+            | "figure"
+            | "footer"
+            | "h1"
+            | "h2"
+            | "h3"
+            | "h4"
+            | "h5"
+            | "h6"
+            | "header"
+            | "hr"
+            | "li"
+            | "ol"
+            | "p"
+            | "pre"
+            | "section"
+            | "summary"
+            | "table"
+            | "tbody"
+            | "thead"
+            | "tfoot"
+            | "tr"
+            | "ul"
+    )
+}
+
+/// Decode the common named entities and numeric character references.
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+
+        let decoded = rest.find(';').filter(|&end| end <= 10).and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => {
+                    let number = entity.strip_prefix('#')?;
+                    let code = match number.strip_prefix(['x', 'X']) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                        None => number.parse().ok()?,
+                    };
+                    char::from_u32(code)
+                }
+            }?;
+            Some((c, end + 1))
+        });
+
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// The text of an HTML fragment: tags are dropped, block elements start new
+/// lines, table cells are separated by tabs and entities are decoded.
+pub(crate) fn html_to_text(html: &str) -> String {
+    let mut text = String::new();
+    for token in tokenize(html) {
+        match token {
+            Token::Text(t) => text.push_str(&decode_entities(t)),
+            Token::Tag { name, closing } => {
+                if is_block_tag(&name) {
+                    text.push('\n');
+                } else if !closing && (name == "td" || name == "th") {
+                    text.push('\t');
+                }
+            }
+        }
+    }
+
+    text.lines()
+        .map(|line| {
+            let cells: Vec<String> = line
+                .split('\t')
+                .map(|cell| cell.split_whitespace().collect::<Vec<_>>().join(" "))
+                .collect();
+            cells.join("\t").trim_matches([' ', '\t']).to_string()
+        })
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+struct OpenDetails {
+    summary: String,
+    in_summary: bool,
+    text: Vec<String>,
+    start_line: Option<usize>,
+}
+
+/// Tracks `<details>` elements across the top-level nodes of a document. The
+/// tags usually sit in separate HTML blocks, with markdown in between.
+#[derive(Default)]
+pub(crate) struct DetailsTracker {
+    open: Vec<OpenDetails>,
+}
+
+impl DetailsTracker {
+    /// Feed an HTML node; returns the elements it closes, innermost first.
+    pub(crate) fn html(&mut self, node: &mdast::Node, value: &str) -> Vec<Details> {
+        let mut closed = Vec::new();
+        // HTML content of the innermost element, converted to text at each
+        // `<details>` or `<summary>` boundary.
+        let mut fragment = String::new();
+
+        for token in tokenize(value) {
+            match token {
+                Token::Tag { name, closing } if name == "details" => {
+                    self.flush(&mut fragment);
+                    if !closing {
+                        self.open.push(OpenDetails {
+                            summary: String::new(),
+                            in_summary: false,
+                            text: Vec::new(),
+                            start_line: node_start_line(node),
+                        });
+                    } else if let Some(details) = self.open.pop() {
+                        let text = details.text.join("\n");
+                        // Nested content belongs to the enclosing element too.
+                        self.text(&text);
+                        closed.push(Details {
+                            summary: html_to_text(&details.summary),
+                            text,
+                            start_line: details.start_line,
+                            end_line: node_end_line(node),
+                        });
+                    }
+                }
+                Token::Tag { name, closing } if name == "summary" => {
+                    self.flush(&mut fragment);
+                    if let Some(details) = self.open.last_mut() {
+                        details.in_summary = !closing;
+                    }
+                }
+                Token::Tag { name, closing } => {
+                    let tag = format!("<{}{}>", if closing { "/" } else { "" }, name);
+                    match self.open.last_mut() {
+                        Some(details) if details.in_summary => details.summary.push_str(&tag),
+                        _ => fragment.push_str(&tag),
+                    }
+                }
+                Token::Text(t) => match self.open.last_mut() {
+                    Some(details) if details.in_summary => details.summary.push_str(t),
+                    _ => fragment.push_str(t),
+                },
+            }
+        }
+
+        self.flush(&mut fragment);
+        closed
+    }
+
+    fn flush(&mut self, fragment: &mut String) {
+        let text = html_to_text(&std::mem::take(fragment));
+        self.text(&text);
+    }
+
+    /// Feed the text of a markdown node inside the innermost open element.
+    pub(crate) fn text(&mut self, text: &str) {
+        if let Some(details) = self.open.last_mut()
+            && !text.trim().is_empty()
+        {
+            details.text.push(text.to_string());
+        }
+    }
+}
+
+/// Push the HTML blocks in `node` or its block-level descendants. HTML
+/// inside paragraphs, headings and tables is inline markup, not a block.
+pub(crate) fn collect_raw_html(node: &mdast::Node, out: &mut Vec<RawHtml>) {
+    match node {
+        mdast::Node::Html(html) => out.push(RawHtml {
+            value: html.value.clone(),
+            start_line: node_start_line(node),
+            end_line: node_end_line(node),
+        }),
+        mdast::Node::Paragraph(_) | mdast::Node::Heading(_) | mdast::Node::Table(_) => {}
+        _ => {
+            if let Some(children) = node.children() {
+                for child in children {
+                    collect_raw_html(child, out);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BlockKind, index_markdown};
+
+    #[test]
+    fn html_is_stripped_to_text() {
+        assert_eq!(
+            html_to_text("<p align=\"center\">Fish &amp; chips<br/>&#8212; <b>fresh</b></p>"),
+            "Fish & chips\n\u{2014} fresh"
+        );
+        assert_eq!(
+            html_to_text(
+                "<table>\n  <tr><th>Name</th><th>Size</th></tr>\n  <tr><td>a</td><td>1 KB</td></tr>\n</table>"
+            ),
+            "Name\tSize\na\t1 KB"
+        );
+        assert_eq!(
+            html_to_text("<!-- hidden --><script>let a = '<b>';</script>x < y"),
+            "x < y"
+        );
+    }
+
+    #[test]
+    fn html_blocks_are_indexed_as_text() {
+        let src = "# Specs\n\n<table>\n<tr><td>Weight</td><td>2 kg</td></tr>\n</table>\n\nPress <kbd>Ctrl</kbd>.\n";
+
+        let sections = index_markdown(src).expect("parse ok");
+        let blocks = &sections[0].body_text;
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].text, "Weight\t2 kg");
+        assert_eq!(blocks[0].kind, BlockKind::Html);
+        assert_eq!(blocks[1].text, "Press Ctrl.");
+
+        // Only the block is raw HTML; tags inside paragraphs are inline.
+        let raw = &sections[0].raw_html;
+        assert_eq!(raw.len(), 1);
+        assert!(raw[0].value.starts_with("<table>"));
+        assert_eq!((raw[0].start_line, raw[0].end_line), (Some(3), Some(5)));
+    }
+
+    #[test]
+    fn details_become_collapsible_sub_sections() {
+        let src = "# FAQ\n\n<details>\n<summary>Why <em>Rust</em>?</summary>\n\nBecause **speed**.\n\n<details><summary>More</summary>Nested.</details>\n\n</details>\n\n<details><summary>Short</summary>All on one line.</details>\n\nAfter.\n";
+
+        let sections = index_markdown(src).expect("parse ok");
+        let details = &sections[0].details;
+        assert_eq!(details.len(), 3, "{:?}", details);
+
+        assert_eq!(details[0].summary, "Why Rust?");
+        assert_eq!(details[0].text, "Because speed.\nNested.");
+        assert_eq!(
+            (details[0].start_line, details[0].end_line),
+            (Some(3), Some(10))
+        );
+        assert_eq!(details[1].summary, "More");
+        assert_eq!(details[1].text, "Nested.");
+        assert_eq!(details[2].summary, "Short");
+        assert_eq!(details[2].text, "All on one line.");
+
+        let texts: Vec<&str> = sections[0]
+            .body_text
+            .iter()
+            .map(|b| b.text.as_str())
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                "Why Rust?",
+                "Because speed.",
+                "More\nNested.",
+                "Short\nAll on one line.",
+                "After."
+            ]
+        );
+    }
+}
@@ -1,53 +1,645 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+mod callouts;
+mod code_meta;
+mod footnotes;
+mod frontmatter;
+mod html;
+mod links;
+mod slug;
+mod text;
+mod tree;
+
+pub use callouts::{Callout, CalloutKind, JsonCallout};
+pub use code_meta::parse_code_meta;
+pub use footnotes::{Footnote, JsonFootnote};
+pub use html::{Details, JsonDetails, JsonRawHtml, RawHtml};
+pub use links::{Image, JsonImage, JsonLink, Link, LinkKind};
+pub use slug::{Slugger, heading_slug};
+pub use text::TextFormat;
+pub use tree::{SectionNode, SectionTree, index_markdown_tree};
+
+/// How a code block was written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CodeBlockKind {
+    /// Between ``` or ~~~ fences.
+    Fenced,
+    /// Indented by four spaces.
+    Indented,
+}
+
 #[derive(Debug, Clone)]
 pub struct CodeBlock {
+    pub kind: CodeBlockKind,
     pub lang: Option<String>,
     pub meta: Option<String>,
+    /// `meta` parsed with [`parse_code_meta`].
+    pub attributes: BTreeMap<String, String>,
+    pub value: String,
+    pub start_line: Option<usize>,
+    pub end_line: Option<usize>,
+    /// 1-based column of the first character.
+    pub start_column: Option<usize>,
+    /// 1-based column just past the last character.
+    pub end_column: Option<usize>,
+    /// 0-based byte offset of the first character in the source.
+    pub start_offset: Option<usize>,
+    /// 0-based byte offset just past the last character (exclusive).
+    pub end_offset: Option<usize>,
+}
+
+/// A `code span` within text. Positions follow the same conventions as
+/// [`CodeBlock`] and cover the backticks.
+#[derive(Debug, Clone)]
+pub struct InlineCode {
     pub value: String,
     pub start_line: Option<usize>,
     pub end_line: Option<usize>,
+    pub start_column: Option<usize>,
+    pub end_column: Option<usize>,
+    pub start_offset: Option<usize>,
+    pub end_offset: Option<usize>,
+}
+
+/// Display (`$$`) or inline (`$`) math. Positions follow the same
+/// conventions as [`CodeBlock`] and cover the dollar signs.
+#[derive(Debug, Clone)]
+pub struct Math {
+    /// The LaTeX source.
+    pub value: String,
+    pub start_line: Option<usize>,
+    pub end_line: Option<usize>,
+    pub start_column: Option<usize>,
+    pub end_column: Option<usize>,
+    pub start_offset: Option<usize>,
+    pub end_offset: Option<usize>,
+}
+
+/// Horizontal alignment of a table column, from the delimiter row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnAlign {
+    Left,
+    Right,
+    Center,
+    None,
+}
+
+impl From<&mdast::AlignKind> for ColumnAlign {
+    fn from(align: &mdast::AlignKind) -> Self {
+        match align {
+            mdast::AlignKind::Left => ColumnAlign::Left,
+            mdast::AlignKind::Right => ColumnAlign::Right,
+            mdast::AlignKind::Center => ColumnAlign::Center,
+            mdast::AlignKind::None => ColumnAlign::None,
+        }
+    }
+}
+
+/// A GFM table with each cell flattened to plain text.
+#[derive(Debug, Clone)]
+pub struct Table {
+    /// One entry per column.
+    pub align: Vec<ColumnAlign>,
+    pub header: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    pub start_line: Option<usize>,
+    pub end_line: Option<usize>,
+}
+
+/// An ordered or unordered list, with nested lists kept under their items.
+#[derive(Debug, Clone)]
+pub struct List {
+    pub ordered: bool,
+    /// Number of the first item of an ordered list.
+    pub start: Option<u32>,
+    pub items: Vec<ListItem>,
+    pub start_line: Option<usize>,
+    pub end_line: Option<usize>,
+}
+
+impl List {
+    /// Plain text with one item per line; nested items are indented by two
+    /// spaces per level.
+    pub fn to_plain_text(&self) -> String {
+        let mut lines = Vec::new();
+        self.push_lines(0, &mut lines);
+        lines.join("\n")
+    }
+
+    fn push_lines(&self, depth: usize, lines: &mut Vec<String>) {
+        for item in &self.items {
+            lines.push(format!("{}{}", "  ".repeat(depth), item.text));
+            for child in &item.children {
+                child.push_lines(depth + 1, lines);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ListItem {
+    /// Text of the item's own content, excluding nested lists.
+    pub text: String,
+    /// GFM task list state: `Some(true)` for `[x]`, `Some(false)` for `[ ]`,
+    /// `None` for ordinary items.
+    pub checked: Option<bool>,
+    /// Lists nested inside this item.
+    pub children: Vec<List>,
+    pub start_line: Option<usize>,
+    pub end_line: Option<usize>,
+}
+
+/// The kind of a top-level content block within a section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockKind {
+    Paragraph,
+    Code,
+    List,
+    Table,
+    Quote,
+    Html,
+    Math,
+    ThematicBreak,
+    /// MDX constructs.
+    Other,
+}
+
+/// One piece of section content, kept in document order.
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub kind: BlockKind,
+    /// Plain text for prose blocks, the source for code, math and HTML, and
+    /// empty for thematic breaks.
+    pub text: String,
+    /// Language of a code block.
+    pub lang: Option<String>,
+    pub start_line: Option<usize>,
+    pub end_line: Option<usize>,
+}
+
+/// A run of prose within a section, such as a paragraph or the flattened
+/// text of a list, with the source range it came from.
+#[derive(Debug, Clone)]
+pub struct TextBlock {
+    /// Plain text, markdown source or HTML, per [`IndexOptions::text_format`].
+    pub text: String,
+    /// The kind of node the text was taken from.
+    pub kind: BlockKind,
+    pub start_line: Option<usize>,
+    pub end_line: Option<usize>,
+    /// Columns and byte offsets follow the same conventions as [`CodeBlock`].
+    pub start_column: Option<usize>,
+    pub end_column: Option<usize>,
+    pub start_offset: Option<usize>,
+    pub end_offset: Option<usize>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Section {
     pub title: String,
     pub level: u8,
-    pub body_text: Vec<String>,
+    /// Unique GitHub-style anchor for the heading, or the explicit
+    /// `{#custom-id}` if one was given. `None` for the preamble.
+    pub anchor: Option<String>,
+    pub body_text: Vec<TextBlock>,
+    /// Fenced and indented code, wherever it is nested.
     pub code_blocks: Vec<CodeBlock>,
+    pub inline_code: Vec<InlineCode>,
+    /// Display math, wherever it is nested.
+    pub math_blocks: Vec<Math>,
+    pub inline_math: Vec<Math>,
+    pub tables: Vec<Table>,
+    pub lists: Vec<List>,
+    pub links: Vec<Link>,
+    pub images: Vec<Image>,
+    /// GitHub alerts and `:::` directive containers. Their text is also part
+    /// of `body_text`.
+    pub callouts: Vec<Callout>,
+    /// Footnotes referenced in the section, in order of first reference.
+    pub footnotes: Vec<Footnote>,
+    /// `<details>` elements, in order of their opening tag. Their text is
+    /// also part of `body_text`.
+    pub details: Vec<Details>,
+    /// HTML blocks as written, wherever they are nested.
+    pub raw_html: Vec<RawHtml>,
+    /// All content blocks in document order, as an alternative to the
+    /// per-type collections above.
+    pub blocks: Vec<Block>,
     pub start_line: Option<usize>,
     pub end_line: Option<usize>,
+    /// Columns and byte offsets follow the same conventions as [`CodeBlock`].
+    pub start_column: Option<usize>,
+    pub end_column: Option<usize>,
+    pub start_offset: Option<usize>,
+    pub end_offset: Option<usize>,
     pub heading_line: Option<usize>,
 }
 
+/// A parsed markdown file: its sections and any frontmatter metadata.
+#[derive(Debug, Clone)]
+pub struct Document {
+    /// Frontmatter converted to JSON, `None` when absent or unparsable.
+    pub metadata: Option<serde_json::Value>,
+    pub sections: Vec<Section>,
+    /// Every footnote definition, referenced or not, in document order.
+    pub footnotes: Vec<Footnote>,
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub struct JsonCodeBlock {
+    pub kind: CodeBlockKind,
+    pub lang: Option<String>,
+    pub meta: Option<String>,
+    pub attributes: BTreeMap<String, String>,
+    pub value: String,
+    pub start_line: Option<usize>,
+    pub end_line: Option<usize>,
+    pub start_column: Option<usize>,
+    pub end_column: Option<usize>,
+    pub start_offset: Option<usize>,
+    pub end_offset: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct JsonInlineCode {
+    pub value: String,
+    pub start_line: Option<usize>,
+    pub end_line: Option<usize>,
+    pub start_column: Option<usize>,
+    pub end_column: Option<usize>,
+    pub start_offset: Option<usize>,
+    pub end_offset: Option<usize>,
+}
+
+impl From<InlineCode> for JsonInlineCode {
+    fn from(code: InlineCode) -> Self {
+        JsonInlineCode {
+            value: code.value,
+            start_line: code.start_line,
+            end_line: code.end_line,
+            start_column: code.start_column,
+            end_column: code.end_column,
+            start_offset: code.start_offset,
+            end_offset: code.end_offset,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct JsonMath {
     pub value: String,
     pub start_line: Option<usize>,
     pub end_line: Option<usize>,
+    pub start_column: Option<usize>,
+    pub end_column: Option<usize>,
+    pub start_offset: Option<usize>,
+    pub end_offset: Option<usize>,
+}
+
+impl From<Math> for JsonMath {
+    fn from(math: Math) -> Self {
+        JsonMath {
+            value: math.value,
+            start_line: math.start_line,
+            end_line: math.end_line,
+            start_column: math.start_column,
+            end_column: math.end_column,
+            start_offset: math.start_offset,
+            end_offset: math.end_offset,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct JsonTable {
+    pub align: Vec<ColumnAlign>,
+    pub header: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    pub start_line: Option<usize>,
+    pub end_line: Option<usize>,
+}
+
+impl From<Table> for JsonTable {
+    fn from(table: Table) -> Self {
+        JsonTable {
+            align: table.align,
+            header: table.header,
+            rows: table.rows,
+            start_line: table.start_line,
+            end_line: table.end_line,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct JsonList {
+    pub ordered: bool,
+    pub start: Option<u32>,
+    pub items: Vec<JsonListItem>,
+    pub start_line: Option<usize>,
+    pub end_line: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct JsonListItem {
+    pub text: String,
+    pub checked: Option<bool>,
+    pub children: Vec<JsonList>,
+    pub start_line: Option<usize>,
+    pub end_line: Option<usize>,
+}
+
+impl From<List> for JsonList {
+    fn from(list: List) -> Self {
+        JsonList {
+            ordered: list.ordered,
+            start: list.start,
+            items: list
+                .items
+                .into_iter()
+                .map(|item| JsonListItem {
+                    text: item.text,
+                    checked: item.checked,
+                    children: item.children.into_iter().map(JsonList::from).collect(),
+                    start_line: item.start_line,
+                    end_line: item.end_line,
+                })
+                .collect(),
+            start_line: list.start_line,
+            end_line: list.end_line,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct JsonBlock {
+    pub kind: BlockKind,
+    pub text: String,
+    pub lang: Option<String>,
+    pub start_line: Option<usize>,
+    pub end_line: Option<usize>,
+}
+
+impl From<Block> for JsonBlock {
+    fn from(block: Block) -> Self {
+        JsonBlock {
+            kind: block.kind,
+            text: block.text,
+            lang: block.lang,
+            start_line: block.start_line,
+            end_line: block.end_line,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct JsonTextBlock {
+    pub text: String,
+    pub kind: BlockKind,
+    pub start_line: Option<usize>,
+    pub end_line: Option<usize>,
+    pub start_column: Option<usize>,
+    pub end_column: Option<usize>,
+    pub start_offset: Option<usize>,
+    pub end_offset: Option<usize>,
+}
+
+impl From<TextBlock> for JsonTextBlock {
+    fn from(block: TextBlock) -> Self {
+        JsonTextBlock {
+            text: block.text,
+            kind: block.kind,
+            start_line: block.start_line,
+            end_line: block.end_line,
+            start_column: block.start_column,
+            end_column: block.end_column,
+            start_offset: block.start_offset,
+            end_offset: block.end_offset,
+        }
+    }
+}
+
+/// The `text_blocks` of a [`JsonDocumentElement`], either with positions or
+/// in the older shape of bare strings.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum JsonTextBlocks {
+    Structured(Vec<JsonTextBlock>),
+    Plain(Vec<String>),
+}
+
+impl JsonTextBlocks {
+    pub fn structured(blocks: Vec<TextBlock>) -> Self {
+        JsonTextBlocks::Structured(blocks.into_iter().map(JsonTextBlock::from).collect())
+    }
+
+    pub fn plain(blocks: Vec<TextBlock>) -> Self {
+        JsonTextBlocks::Plain(blocks.into_iter().map(|block| block.text).collect())
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub struct JsonDocumentElement {
+    /// Position of this element in the emitted array.
+    pub id: usize,
+    /// `id` of the enclosing section's element, `None` for top-level sections.
+    pub parent_id: Option<usize>,
     pub file_path: String,
     pub header: String,
-    pub text_blocks: Vec<String>,
+    pub level: u8,
+    pub anchor: Option<String>,
+    /// Headers of all enclosing sections, outermost first.
+    pub breadcrumbs: Vec<String>,
+    pub text_blocks: JsonTextBlocks,
     pub code_blocks: Vec<JsonCodeBlock>,
+    pub inline_code: Vec<JsonInlineCode>,
+    pub math_blocks: Vec<JsonMath>,
+    pub inline_math: Vec<JsonMath>,
+    pub tables: Vec<JsonTable>,
+    pub lists: Vec<JsonList>,
+    pub links: Vec<JsonLink>,
+    pub images: Vec<JsonImage>,
+    pub callouts: Vec<JsonCallout>,
+    pub footnotes: Vec<JsonFootnote>,
+    pub details: Vec<JsonDetails>,
+    /// HTML blocks as written; only present when requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_html: Option<Vec<JsonRawHtml>>,
+    /// Ordered content blocks; only present when requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blocks: Option<Vec<JsonBlock>>,
     pub start_line: Option<usize>,
     pub end_line: Option<usize>,
+    pub start_column: Option<usize>,
+    pub end_column: Option<usize>,
+    pub start_offset: Option<usize>,
+    pub end_offset: Option<usize>,
     pub heading_line: Option<usize>,
+    /// Frontmatter of the file this section belongs to.
+    pub metadata: Option<serde_json::Value>,
+}
+
+/// A [`JsonDocumentElement`] with its nested subsections, as emitted by the
+/// CLI's `--tree` mode.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct JsonSectionNode {
+    #[serde(flatten)]
+    pub element: JsonDocumentElement,
+    pub children: Vec<JsonSectionNode>,
+}
+
+impl JsonSectionNode {
+    /// Nest flat elements under their `parent_id`, keeping document order.
+    ///
+    /// Elements whose parent is missing from `elements` become roots.
+    pub fn nest(elements: Vec<JsonDocumentElement>) -> Vec<JsonSectionNode> {
+        let positions: HashMap<usize, usize> = elements
+            .iter()
+            .enumerate()
+            .map(|(position, element)| (element.id, position))
+            .collect();
+        let mut children: Vec<Vec<JsonSectionNode>> = elements.iter().map(|_| Vec::new()).collect();
+        let mut roots = Vec::new();
+
+        // Walk backwards so every child is complete before its parent is.
+        for (position, element) in elements.into_iter().enumerate().rev() {
+            let mut own_children = std::mem::take(&mut children[position]);
+            own_children.reverse();
+
+            let parent = element.parent_id.and_then(|id| positions.get(&id).copied());
+            let node = JsonSectionNode {
+                element,
+                children: own_children,
+            };
+
+            match parent {
+                Some(parent) => children[parent].push(node),
+                None => roots.push(node),
+            }
+        }
+
+        roots.reverse();
+        roots
+    }
 }
 
 use markdown::message::Message;
-use markdown::{self, ParseOptions, mdast};
+use markdown::{self, Constructs, ParseOptions, mdast};
+use text::{PlainTextRenderer, TextFormatter};
+
+/// Markdown extensions to recognize on top of CommonMark, plus how some
+/// content is indexed.
+///
+/// Everything but [`IndexOptions::image_alt_text`] is off by default,
+/// matching [`index_markdown`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexOptions {
+    /// GitHub flavored markdown: tables, strikethrough, footnotes, task lists
+    /// and autolink literals.
+    pub gfm: bool,
+    /// MDX: JSX, expressions and ESM. Turns off indented code, autolinks and
+    /// HTML, which conflict with MDX syntax.
+    pub mdx: bool,
+    /// `$inline$` and `$$display$$` math.
+    pub math: bool,
+    /// YAML (`---`) and TOML (`+++`) frontmatter at the start of the document.
+    pub frontmatter: bool,
+    /// Leave `code spans` out of prose text. They are always recorded in
+    /// [`Section::inline_code`].
+    pub strip_inline_code: bool,
+    /// Follow link text with its URL in prose text: `text (url)`.
+    pub link_urls: bool,
+    /// Include the alt text of images in prose text, so diagrams and
+    /// screenshots are searchable. On by default. Images are always recorded
+    /// in [`Section::images`].
+    pub image_alt_text: bool,
+    /// Keep footnote references in prose text as `[^label]`.
+    pub footnote_markers: bool,
+    /// Keep the LaTeX source of math in prose text. It is always recorded in
+    /// [`Section::math_blocks`] and [`Section::inline_math`].
+    pub math_text: bool,
+    /// Format of [`Section::body_text`]. The options above only apply to
+    /// [`TextFormat::Plain`].
+    pub text_format: TextFormat,
+}
+
+impl Default for IndexOptions {
+    fn default() -> Self {
+        IndexOptions {
+            gfm: false,
+            mdx: false,
+            math: false,
+            frontmatter: false,
+            strip_inline_code: false,
+            link_urls: false,
+            image_alt_text: true,
+            footnote_markers: false,
+            math_text: false,
+            text_format: TextFormat::default(),
+        }
+    }
+}
+
+impl IndexOptions {
+    /// The `markdown` crate parse options enabling the selected constructs.
+    pub fn parse_options(&self) -> ParseOptions {
+        let mut constructs = if self.mdx {
+            Constructs::mdx()
+        } else {
+            Constructs::default()
+        };
+
+        if self.gfm {
+            constructs.gfm_autolink_literal = true;
+            constructs.gfm_footnote_definition = true;
+            constructs.gfm_label_start_footnote = true;
+            constructs.gfm_strikethrough = true;
+            constructs.gfm_table = true;
+            constructs.gfm_task_list_item = true;
+        }
+        if self.math {
+            constructs.math_flow = true;
+            constructs.math_text = true;
+        }
+        if self.frontmatter {
+            constructs.frontmatter = true;
+        }
+
+        ParseOptions {
+            constructs,
+            ..ParseOptions::default()
+        }
+    }
+}
 
 /// Parse a markdown document into sections, each starting at a heading.
 /// All text / code until the next heading belongs to that section.
 ///
 /// Sections are *flat*: nested headings become separate sections, but
 /// each still carries its heading level (`#` = 1, `##` = 2, …).
-
+/// Use [`index_markdown_tree`] to get them arranged by nesting instead.
 pub fn index_markdown(src: &str) -> Result<Vec<Section>, Message> {
-    let ast = markdown::to_mdast(src, &ParseOptions::default())?;
+    index_markdown_with_options(src, &IndexOptions::default())
+}
+
+/// Like [`index_markdown`], but recognizes the extensions selected in `options`.
+pub fn index_markdown_with_options(
+    src: &str,
+    options: &IndexOptions,
+) -> Result<Vec<Section>, Message> {
+    Ok(index_document(src, options)?.sections)
+}
+
+/// Parse a markdown document into its sections plus document-level metadata.
+///
+/// With [`IndexOptions::frontmatter`] enabled, YAML or TOML frontmatter is
+/// parsed into [`Document::metadata`] and kept out of the section text.
+pub fn index_document(src: &str, options: &IndexOptions) -> Result<Document, Message> {
+    let ast = markdown::to_mdast(src, &options.parse_options())?;
 
     let root = match ast {
         mdast::Node::Root(root) => root,
@@ -56,6 +648,30 @@ pub fn index_markdown(src: &str) -> Result<Vec<Section>, Message> {
 
     let mut sections: Vec<Section> = Vec::new();
     let mut current: Option<Section> = None;
+    let mut metadata: Option<serde_json::Value> = None;
+
+    let mut definitions = links::Definitions::new();
+    for node in &root.children {
+        links::collect_definitions(node, &mut definitions);
+    }
+    let directive_lines = callouts::directive_lines(src);
+    let renderer =
+        PlainTextRenderer::new(options, &definitions).with_directive_lines(&directive_lines);
+    let mut footnote_definitions = footnotes::FootnoteDefinitions::new();
+    for node in &root.children {
+        footnotes::collect_definitions(
+            src,
+            node,
+            &renderer,
+            &definitions,
+            &mut footnote_definitions,
+        );
+    }
+    let formatter = TextFormatter::new(src, options, &definitions);
+    let mut directives = callouts::find_directives(src, &options.parse_options(), &renderer)
+        .into_iter()
+        .peekable();
+    let mut details = html::DetailsTracker::default();
 
     for node in &root.children {
         match node {
@@ -66,118 +682,111 @@ pub fn index_markdown(src: &str) -> Result<Vec<Section>, Message> {
                     sections.push(sec);
                 }
 
-                let title = node_to_plain_text(node);
+                // The anchor holds an explicit `{#custom-id}` for now; the
+                // rest are generated once all headings are known.
+                let (title, anchor) = slug::split_custom_id(&node_to_plain_text(node));
 
                 current = Some(Section {
                     title,
                     level: h.depth,
+                    anchor,
                     body_text: Vec::new(),
                     code_blocks: Vec::new(),
+                    inline_code: Vec::new(),
+                    math_blocks: Vec::new(),
+                    inline_math: Vec::new(),
+                    tables: Vec::new(),
+                    lists: Vec::new(),
+                    links: Vec::new(),
+                    images: Vec::new(),
+                    callouts: Vec::new(),
+                    footnotes: Vec::new(),
+                    details: Vec::new(),
+                    raw_html: Vec::new(),
+                    blocks: Vec::new(),
                     start_line: node_start_line(node),
                     end_line: node_end_line(node),
+                    start_column: node_start_column(node),
+                    end_column: node_end_column(node),
+                    start_offset: node_start_offset(node),
+                    end_offset: node_end_offset(node),
                     heading_line: node_start_line(node),
                 });
             }
 
             // === Paragraphs become body text ===
             mdast::Node::Paragraph(_) => {
-                let text = node_to_plain_text(node);
-                if text.trim().is_empty() {
-                    continue;
-                }
+                let text = renderer.render(node);
+                if !text.trim().is_empty() {
+                    let sec = current.get_or_insert_with(|| preamble_section(node));
 
-                let sec = current.get_or_insert_with(|| Section {
-                    title: String::from("(preamble)"),
-                    level: 0,
-                    body_text: Vec::new(),
-                    code_blocks: Vec::new(),
-                    start_line: node_start_line(node),
-                    end_line: node_end_line(node),
-                    heading_line: None,
-                });
+                    if !sec.body_text.is_empty() {
+                        //sec.body_text.push_str("\n\n");
+                    }
+                    sec.body_text.push(text_block(node, text, &formatter));
+                    update_section_end(sec, node);
 
-                if !sec.body_text.is_empty() {
-                    //sec.body_text.push_str("\n\n");
+                    collect_code_blocks(src, node, &mut sec.code_blocks);
                 }
-                sec.body_text.push(text);
-                update_section_end_line(sec, node_end_line(node));
+            }
+
+            // === Top-level fenced and indented code blocks ===
+            mdast::Node::Code(_) => {
+                // Code before any heading -> attach to a synthetic preamble section
+                let sec = current.get_or_insert_with(|| preamble_section(node));
+                collect_code_blocks(src, node, &mut sec.code_blocks);
+                update_section_end(sec, node);
+            }
+
+            // === Footnotes are indexed where they are referenced (below) ===
+            mdast::Node::FootnoteDefinition(_) => continue,
 
-                collect_inline_code_blocks(node, &mut sec.code_blocks);
+            // === Frontmatter is metadata, not text ===
+            mdast::Node::Yaml(yaml) => {
+                metadata = frontmatter::yaml_to_json(&yaml.value);
             }
+            mdast::Node::Toml(toml) => {
+                metadata = frontmatter::toml_to_json(&toml.value);
+            }
+
+            // === Tables keep their cells, plus flattened text for search ===
+            mdast::Node::Table(table) => {
+                let sec = current.get_or_insert_with(|| preamble_section(node));
+                sec.tables.push(table_from_node(node, table));
 
-            // === Top-level fenced code blocks ===
-            mdast::Node::Code(code) => {
-                if let Some(sec) = current.as_mut() {
-                    sec.code_blocks.push(CodeBlock {
-                        lang: code.lang.clone(),
-                        meta: code.meta.clone(),
-                        value: code.value.clone(),
-                        start_line: node_start_line(node),
-                        end_line: node_end_line(node),
-                    });
-                    update_section_end_line(sec, node_end_line(node));
-                } else {
-                    // Code before any heading -> attach to a synthetic preamble section
-                    let sec = Section {
-                        title: String::from("(preamble)"),
-                        level: 0,
-                        body_text: Vec::new(),
-                        code_blocks: vec![CodeBlock {
-                            lang: code.lang.clone(),
-                            meta: code.meta.clone(),
-                            value: code.value.clone(),
-                            start_line: node_start_line(node),
-                            end_line: node_end_line(node),
-                        }],
-                        start_line: node_start_line(node),
-                        end_line: node_end_line(node),
-                        heading_line: None,
-                    };
-                    current = Some(sec);
+                let text = renderer.render(node);
+                if !text.trim().is_empty() {
+                    sec.body_text.push(text_block(node, text, &formatter));
                 }
+                collect_code_blocks(src, node, &mut sec.code_blocks);
+                update_section_end(sec, node);
             }
 
-            // === Inline code is indexed like fenced code ===
-            mdast::Node::InlineCode(code) => {
-                let inline_block = CodeBlock {
-                    lang: None,
-                    meta: None,
-                    value: code.value.clone(),
-                    start_line: node_start_line(node),
-                    end_line: node_end_line(node),
-                };
-
-                if let Some(sec) = current.as_mut() {
-                    sec.code_blocks.push(inline_block);
-                    update_section_end_line(sec, node_end_line(node));
-                } else {
-                    let sec = Section {
-                        title: String::from("(preamble)"),
-                        level: 0,
-                        body_text: Vec::new(),
-                        code_blocks: vec![inline_block],
-                        start_line: node_start_line(node),
-                        end_line: node_end_line(node),
-                        heading_line: None,
-                    };
-                    current = Some(sec);
+            // === Lists keep their structure, plus one line per item as text ===
+            mdast::Node::List(list) => {
+                let list = list_from_node(node, list, &renderer);
+                let text = list.to_plain_text();
+
+                let sec = current.get_or_insert_with(|| preamble_section(node));
+                if !text.trim().is_empty() {
+                    sec.body_text.push(text_block(node, text, &formatter));
                 }
+                sec.lists.push(list);
+                collect_code_blocks(src, node, &mut sec.code_blocks);
+                update_section_end(sec, node);
             }
 
             // === Block/inline content we treat as extra text ===
             //
             // We just flatten them to plain text and append to current section /
-            // preamble. `node_to_plain_text` will walk their children.
+            // preamble. The renderer will walk their children, and
+            // `collect_code_blocks` picks up code nested inside containers such
+            // as blockquotes.
             mdast::Node::Blockquote(_)
-            | mdast::Node::FootnoteDefinition(_)
             | mdast::Node::MdxJsxFlowElement(_)
-            | mdast::Node::List(_)
             | mdast::Node::MdxjsEsm(_)
-            | mdast::Node::Toml(_)
-            | mdast::Node::Yaml(_)
             | mdast::Node::Math(_)
             | mdast::Node::MdxFlowExpression(_)
-            | mdast::Node::Table(_)
             | mdast::Node::TableRow(_)
             | mdast::Node::TableCell(_)
             | mdast::Node::ListItem(_)
@@ -187,6 +796,7 @@ pub fn index_markdown(src: &str) -> Result<Vec<Section>, Message> {
             // Phrasing / inline-like nodes (normally don’t show up at root,
             // but we handle them anyway to make the match exhaustive):
             | mdast::Node::Break(_)
+            | mdast::Node::InlineCode(_)
             | mdast::Node::InlineMath(_)
             | mdast::Node::Delete(_)
             | mdast::Node::Emphasis(_)
@@ -199,28 +809,17 @@ pub fn index_markdown(src: &str) -> Result<Vec<Section>, Message> {
             | mdast::Node::LinkReference(_)
             | mdast::Node::Strong(_)
             | mdast::Node::Text(_) => {
-                let text = node_to_plain_text(node);
-                if text.trim().is_empty() {
-                    continue;
-                }
-
-                if let Some(sec) = current.as_mut() {
-                    if !sec.body_text.is_empty() {
-                        //sec.body_text.push_str("\n\n");
+                let text = renderer.render(node);
+                let mut code_blocks = Vec::new();
+                collect_code_blocks(src, node, &mut code_blocks);
+
+                if !text.trim().is_empty() || !code_blocks.is_empty() {
+                    let sec = current.get_or_insert_with(|| preamble_section(node));
+                    if !text.trim().is_empty() {
+                        sec.body_text.push(text_block(node, text, &formatter));
                     }
-                    sec.body_text.push(text);
-                    update_section_end_line(sec, node_end_line(node));
-                } else {
-                    let preamble = Section {
-                        title: String::from("(preamble)"),
-                        level: 0,
-                        body_text: vec![text],
-                        code_blocks: Vec::new(),
-                        start_line: node_start_line(node),
-                        end_line: node_end_line(node),
-                        heading_line: None,
-                    };
-                    current = Some(preamble);
+                    sec.code_blocks.extend(code_blocks);
+                    update_section_end(sec, node);
                 }
             }
 
@@ -230,6 +829,124 @@ pub fn index_markdown(src: &str) -> Result<Vec<Section>, Message> {
                 // no-op
             }
         }
+
+        // Content nodes also become a block of whatever section they ended up
+        // in. Nodes that did not open a preamble (a leading HTML comment, say)
+        // do not get one just for their block.
+        if let Some(block) = block_from_node(node, &renderer)
+            && let Some(sec) = current.as_mut()
+        {
+            sec.blocks.push(block);
+            update_section_end(sec, node);
+        }
+
+        // Links anywhere inside the node belong to the section it ended up in.
+        // Like text, anything collected into a section extends its range.
+        let mut node_links = Vec::new();
+        links::collect_links(node, &definitions, &mut node_links);
+        if !node_links.is_empty() {
+            let sec = current.get_or_insert_with(|| preamble_section(node));
+            sec.links.extend(node_links);
+            update_section_end(sec, node);
+        }
+
+        // Likewise for images, callouts, details, raw HTML, code spans, math
+        // and footnotes.
+        let mut node_images = Vec::new();
+        links::collect_images(node, &definitions, &mut node_images);
+        if !node_images.is_empty() {
+            let sec = current.get_or_insert_with(|| preamble_section(node));
+            sec.images.extend(node_images);
+            update_section_end(sec, node);
+        }
+
+        // Alerts are blockquotes, and a directive belongs to the node holding
+        // its opening line.
+        let mut node_callouts: Vec<Callout> =
+            callouts::alert(node, &renderer).into_iter().collect();
+        while let Some(directive) = directives.next_if(|d| d.start_line <= node_end_line(node)) {
+            node_callouts.push(directive);
+        }
+        if !node_callouts.is_empty() {
+            let sec = current.get_or_insert_with(|| preamble_section(node));
+            sec.callouts.extend(node_callouts);
+            update_section_end(sec, node);
+        }
+
+        // A `<details>` element usually spans several nodes: HTML blocks for
+        // its tags and markdown in between. It belongs to the section it
+        // closes in.
+        let node_details = match node {
+            mdast::Node::Html(html) => details.html(node, &html.value),
+            _ => {
+                details.text(&renderer.render(node));
+                Vec::new()
+            }
+        };
+        if !node_details.is_empty() {
+            let sec = current.get_or_insert_with(|| preamble_section(node));
+            for d in node_details {
+                let at = sec
+                    .details
+                    .partition_point(|e| e.start_line <= d.start_line);
+                sec.details.insert(at, d);
+            }
+            update_section_end(sec, node);
+        }
+
+        // Like blocks, raw HTML alone does not open a preamble.
+        if let Some(sec) = current.as_mut() {
+            let before = sec.raw_html.len();
+            html::collect_raw_html(node, &mut sec.raw_html);
+            if sec.raw_html.len() > before {
+                update_section_end(sec, node);
+            }
+        }
+
+        // Code spans and math are kept apart from block code and prose.
+        let mut node_inline_code = Vec::new();
+        collect_inline_code(node, &mut node_inline_code);
+        if !node_inline_code.is_empty() {
+            let sec = current.get_or_insert_with(|| preamble_section(node));
+            sec.inline_code.extend(node_inline_code);
+            update_section_end(sec, node);
+        }
+
+        let mut node_math_blocks = Vec::new();
+        let mut node_inline_math = Vec::new();
+        collect_math(node, &mut node_math_blocks, &mut node_inline_math);
+        if !node_math_blocks.is_empty() || !node_inline_math.is_empty() {
+            let sec = current.get_or_insert_with(|| preamble_section(node));
+            sec.math_blocks.extend(node_math_blocks);
+            sec.inline_math.extend(node_inline_math);
+            update_section_end(sec, node);
+        }
+
+        // Each referenced footnote is attached once per section, along with
+        // the code, math and images in its definition. Its links stay with
+        // the footnote.
+        let mut node_footnotes = Vec::new();
+        footnotes::collect_references(node, &footnote_definitions, &mut node_footnotes);
+        if !node_footnotes.is_empty() {
+            let sec = current.get_or_insert_with(|| preamble_section(node));
+            for definition in node_footnotes {
+                let footnote = &definition.footnote;
+                if sec.footnotes.iter().any(|f| f.label == footnote.label) {
+                    continue;
+                }
+                sec.footnotes.push(footnote.clone());
+                sec.code_blocks
+                    .extend(definition.code_blocks.iter().cloned());
+                sec.inline_code
+                    .extend(definition.inline_code.iter().cloned());
+                sec.math_blocks
+                    .extend(definition.math_blocks.iter().cloned());
+                sec.inline_math
+                    .extend(definition.inline_math.iter().cloned());
+                sec.images.extend(definition.images.iter().cloned());
+            }
+            update_section_end(sec, node);
+        }
     }
 
     // Flush last section.
@@ -237,7 +954,182 @@ pub fn index_markdown(src: &str) -> Result<Vec<Section>, Message> {
         sections.push(sec);
     }
 
-    Ok(sections)
+    // Custom ids are reserved first, so a generated anchor never takes one,
+    // even when the heading with the custom id comes later.
+    let mut slugger = Slugger::new();
+    for custom in sections.iter().filter_map(|s| s.anchor.as_deref()) {
+        slugger.reserve(custom);
+    }
+    for sec in sections.iter_mut().filter(|s| s.heading_line.is_some()) {
+        if sec.anchor.is_none() {
+            sec.anchor = Some(slugger.slug(&sec.title));
+        }
+    }
+
+    let mut footnotes: Vec<Footnote> = footnote_definitions
+        .into_values()
+        .map(|definition| definition.footnote)
+        .collect();
+    footnotes.sort_by_key(|f| f.start_line);
+
+    Ok(Document {
+        metadata,
+        sections,
+        footnotes,
+    })
+}
+
+/// Synthetic section for content that appears before the first heading.
+fn preamble_section(node: &mdast::Node) -> Section {
+    Section {
+        title: String::from("(preamble)"),
+        level: 0,
+        anchor: None,
+        body_text: Vec::new(),
+        code_blocks: Vec::new(),
+        inline_code: Vec::new(),
+        math_blocks: Vec::new(),
+        inline_math: Vec::new(),
+        tables: Vec::new(),
+        lists: Vec::new(),
+        links: Vec::new(),
+        images: Vec::new(),
+        callouts: Vec::new(),
+        footnotes: Vec::new(),
+        details: Vec::new(),
+        raw_html: Vec::new(),
+        blocks: Vec::new(),
+        start_line: node_start_line(node),
+        end_line: node_end_line(node),
+        start_column: node_start_column(node),
+        end_column: node_end_column(node),
+        start_offset: node_start_offset(node),
+        end_offset: node_end_offset(node),
+        heading_line: None,
+    }
+}
+
+fn table_from_node(node: &mdast::Node, table: &mdast::Table) -> Table {
+    let mut rows = table.children.iter().map(|row| {
+        row.children()
+            .map(|cells| cells.iter().map(node_to_plain_text).collect())
+            .unwrap_or_default()
+    });
+
+    Table {
+        align: table.align.iter().map(ColumnAlign::from).collect(),
+        header: rows.next().unwrap_or_default(),
+        rows: rows.collect(),
+        start_line: node_start_line(node),
+        end_line: node_end_line(node),
+    }
+}
+
+/// The kind of block a top-level node makes, or `None` for headings,
+/// definitions (footnotes included) and frontmatter, which are not section
+/// content.
+fn block_kind(node: &mdast::Node) -> Option<BlockKind> {
+    Some(match node {
+        mdast::Node::Paragraph(_) => BlockKind::Paragraph,
+        mdast::Node::Code(_) => BlockKind::Code,
+        mdast::Node::List(_) => BlockKind::List,
+        mdast::Node::Table(_) => BlockKind::Table,
+        mdast::Node::Blockquote(_) => BlockKind::Quote,
+        mdast::Node::Html(_) => BlockKind::Html,
+        mdast::Node::Math(_) => BlockKind::Math,
+        mdast::Node::ThematicBreak(_) => BlockKind::ThematicBreak,
+        mdast::Node::Heading(_)
+        | mdast::Node::Definition(_)
+        | mdast::Node::FootnoteDefinition(_)
+        | mdast::Node::Yaml(_)
+        | mdast::Node::Toml(_)
+        | mdast::Node::Root(_) => return None,
+        _ => BlockKind::Other,
+    })
+}
+
+/// The block for a top-level node; see [`block_kind`].
+fn block_from_node(node: &mdast::Node, renderer: &PlainTextRenderer) -> Option<Block> {
+    let kind = block_kind(node)?;
+    let (text, lang) = match node {
+        mdast::Node::Code(code) => (code.value.clone(), code.lang.clone()),
+        mdast::Node::List(list) => (list_from_node(node, list, renderer).to_plain_text(), None),
+        mdast::Node::Html(html) => (html.value.clone(), None),
+        mdast::Node::Math(math) => (math.value.clone(), None),
+        mdast::Node::ThematicBreak(_) => (String::new(), None),
+        _ => (renderer.render(node), None),
+    };
+
+    Some(Block {
+        kind,
+        text,
+        lang,
+        start_line: node_start_line(node),
+        end_line: node_end_line(node),
+    })
+}
+
+/// Section text taken from `node`, covering the node's source range.
+/// `text` is the node's plain text.
+fn text_block(node: &mdast::Node, text: String, formatter: &TextFormatter) -> TextBlock {
+    TextBlock {
+        text: formatter.format(node, text),
+        kind: block_kind(node).unwrap_or(BlockKind::Other),
+        start_line: node_start_line(node),
+        end_line: node_end_line(node),
+        start_column: node_start_column(node),
+        end_column: node_end_column(node),
+        start_offset: node_start_offset(node),
+        end_offset: node_end_offset(node),
+    }
+}
+
+fn list_from_node(node: &mdast::Node, list: &mdast::List, renderer: &PlainTextRenderer) -> List {
+    let items = list
+        .children
+        .iter()
+        .filter_map(|child| match child {
+            mdast::Node::ListItem(item) => Some(list_item_from_node(child, item, renderer)),
+            _ => None,
+        })
+        .collect();
+
+    List {
+        ordered: list.ordered,
+        start: if list.ordered { list.start } else { None },
+        items,
+        start_line: node_start_line(node),
+        end_line: node_end_line(node),
+    }
+}
+
+fn list_item_from_node(
+    node: &mdast::Node,
+    item: &mdast::ListItem,
+    renderer: &PlainTextRenderer,
+) -> ListItem {
+    let mut texts = Vec::new();
+    let mut children = Vec::new();
+
+    for child in &item.children {
+        match child {
+            mdast::Node::List(list) => children.push(list_from_node(child, list, renderer)),
+            _ => {
+                let text = renderer.render(child);
+                if !text.trim().is_empty() {
+                    texts.push(text);
+                }
+            }
+        }
+    }
+
+    ListItem {
+        text: texts.join(" "),
+        checked: item.checked,
+        children,
+        start_line: node_start_line(node),
+        end_line: node_end_line(node),
+    }
 }
 
 fn node_start_line(node: &mdast::Node) -> Option<usize> {
@@ -248,54 +1140,132 @@ fn node_end_line(node: &mdast::Node) -> Option<usize> {
     node.position().map(|p| p.end.line)
 }
 
-fn update_section_end_line(section: &mut Section, candidate_end_line: Option<usize>) {
-    match (section.end_line, candidate_end_line) {
-        (Some(current), Some(candidate)) => section.end_line = Some(current.max(candidate)),
-        (None, Some(candidate)) => section.end_line = Some(candidate),
-        _ => {}
+fn node_start_column(node: &mdast::Node) -> Option<usize> {
+    node.position().map(|p| p.start.column)
+}
+
+fn node_end_column(node: &mdast::Node) -> Option<usize> {
+    node.position().map(|p| p.end.column)
+}
+
+fn node_start_offset(node: &mdast::Node) -> Option<usize> {
+    node.position().map(|p| p.start.offset)
+}
+
+fn node_end_offset(node: &mdast::Node) -> Option<usize> {
+    node.position().map(|p| p.end.offset)
+}
+
+/// Extend the section's end to cover `node`, if the node ends later.
+fn update_section_end(section: &mut Section, node: &mdast::Node) {
+    let Some(end) = node.position().map(|p| &p.end) else {
+        return;
+    };
+
+    if section
+        .end_offset
+        .is_none_or(|current| end.offset > current)
+    {
+        section.end_line = Some(end.line);
+        section.end_column = Some(end.column);
+        section.end_offset = Some(end.offset);
     }
 }
+
+/// The code block for a `Code` node, `None` for anything else.
+fn code_block_from_node(src: &str, node: &mdast::Node) -> Option<CodeBlock> {
+    let (kind, lang, meta, value) = match node {
+        mdast::Node::Code(code) => {
+            // mdast does not tell fenced and indented code apart; the source does.
+            let opening = node_start_offset(node)
+                .and_then(|offset| src.get(offset..))
+                .unwrap_or_default();
+            let kind = if opening.starts_with("```") || opening.starts_with("~~~") {
+                CodeBlockKind::Fenced
+            } else {
+                CodeBlockKind::Indented
+            };
+            (kind, code.lang.clone(), code.meta.clone(), &code.value)
+        }
+        _ => return None,
+    };
+
+    Some(CodeBlock {
+        kind,
+        attributes: meta.as_deref().map(parse_code_meta).unwrap_or_default(),
+        lang,
+        meta,
+        value: value.clone(),
+        start_line: node_start_line(node),
+        end_line: node_end_line(node),
+        start_column: node_start_column(node),
+        end_column: node_end_column(node),
+        start_offset: node_start_offset(node),
+        end_offset: node_end_offset(node),
+    })
+}
+
 /// Collect human-readable text from a node (drops formatting, links, etc.).
+///
+/// Used for titles, link texts and table cells, which keep code spans and
+/// ignore the text options; section prose goes through the
+/// [`PlainTextRenderer`] built from the [`IndexOptions`] instead.
 fn node_to_plain_text(node: &mdast::Node) -> String {
-    let mut out = String::new();
-    collect_text(node, &mut out);
-    out
+    PlainTextRenderer::new(&IndexOptions::default(), &links::Definitions::new()).render(node)
 }
 
-fn collect_inline_code_blocks(node: &mdast::Node, out: &mut Vec<CodeBlock>) {
-    match node {
-        mdast::Node::InlineCode(code) => out.push(CodeBlock {
-            lang: None,
-            meta: None,
+/// Collect every code span inside `node`, in document order.
+fn collect_inline_code(node: &mdast::Node, out: &mut Vec<InlineCode>) {
+    if let mdast::Node::InlineCode(code) = node {
+        out.push(InlineCode {
             value: code.value.clone(),
             start_line: node_start_line(node),
             end_line: node_end_line(node),
-        }),
-        _ => {
-            if let Some(children) = node.children() {
-                for child in children {
-                    collect_inline_code_blocks(child, out);
-                }
-            }
+            start_column: node_start_column(node),
+            end_column: node_end_column(node),
+            start_offset: node_start_offset(node),
+            end_offset: node_end_offset(node),
+        });
+    } else if let Some(children) = node.children() {
+        for child in children {
+            collect_inline_code(child, out);
         }
     }
 }
 
-fn collect_text(node: &mdast::Node, out: &mut String) {
-    match node {
-        mdast::Node::Text(t) => {
-            out.push_str(&t.value);
-        }
-        mdast::Node::InlineCode(c) => {
-            // Treat inline code as text for indexing
-            out.push_str(&c.value);
-        }
+/// Collect display and inline math anywhere inside `node`, in document order.
+fn collect_math(node: &mdast::Node, display: &mut Vec<Math>, inline: &mut Vec<Math>) {
+    let (value, out) = match node {
+        mdast::Node::Math(math) => (&math.value, display),
+        mdast::Node::InlineMath(math) => (&math.value, inline),
         _ => {
             if let Some(children) = node.children() {
                 for child in children {
-                    collect_text(child, out);
+                    collect_math(child, display, inline);
                 }
             }
+            return;
+        }
+    };
+
+    out.push(Math {
+        value: value.clone(),
+        start_line: node_start_line(node),
+        end_line: node_end_line(node),
+        start_column: node_start_column(node),
+        end_column: node_end_column(node),
+        start_offset: node_start_offset(node),
+        end_offset: node_end_offset(node),
+    });
+}
+
+/// Collect fenced and indented code anywhere inside `node`, in document order.
+fn collect_code_blocks(src: &str, node: &mdast::Node, out: &mut Vec<CodeBlock>) {
+    if let Some(code_block) = code_block_from_node(src, node) {
+        out.push(code_block);
+    } else if let Some(children) = node.children() {
+        for child in children {
+            collect_code_blocks(src, child, out);
         }
     }
 }
@@ -305,6 +1275,10 @@ mod tests {
     use super::*;
     use serde_json::json;
 
+    fn texts(section: &Section) -> Vec<&str> {
+        section.body_text.iter().map(|b| b.text.as_str()).collect()
+    }
+
     #[test]
     fn heading_paragraph_and_code_are_grouped_into_one_section() {
         let src = r#"
@@ -332,7 +1306,7 @@ println!(\"hello\");
 
         // Body text
         assert!(
-            s.body_text.contains(&"This is the intro text.".to_string()),
+            texts(s).contains(&"This is the intro text."),
             "body_text = {:?}",
             s.body_text
         );
@@ -371,12 +1345,16 @@ print(\"hi\")
         let intro = &sections[0];
         assert_eq!(intro.title, "Intro");
         assert_eq!(intro.level, 1);
-        assert!(intro.body_text.contains(&"Intro text.".to_string()));
+        assert!(texts(intro).contains(&"Intro text."));
 
         let details = &sections[1];
         assert_eq!(details.title, "Details");
         assert_eq!(details.level, 2);
-        assert!(details.body_text[0].contains(&"More details here".to_string()));
+        assert!(
+            details.body_text[0]
+                .text
+                .contains(&"More details here".to_string())
+        );
         assert_eq!(details.code_blocks.len(), 1);
         assert_eq!(details.code_blocks[0].lang.as_deref(), Some("python"));
         // assert!(details.code_blocks[0].value.contains("print(\"hi\")"));
@@ -406,7 +1384,11 @@ More text under heading.
         let preamble = &sections[0];
         assert_eq!(preamble.title, "(preamble)");
         assert_eq!(preamble.level, 0);
-        assert!(preamble.body_text[0].contains(&"text before any heading".to_string()));
+        assert!(
+            preamble.body_text[0]
+                .text
+                .contains(&"text before any heading".to_string())
+        );
         assert_eq!(preamble.code_blocks.len(), 1);
         assert_eq!(preamble.code_blocks[0].lang.as_deref(), Some("bash"));
         // assert!(preamble.code_blocks[0].value.contains("echo \"hello\""));
@@ -414,7 +1396,11 @@ More text under heading.
         let heading = &sections[1];
         assert_eq!(heading.title, "Heading");
         assert_eq!(heading.level, 1);
-        assert!(heading.body_text[0].contains(&"More text under heading".to_string()));
+        assert!(
+            heading.body_text[0]
+                .text
+                .contains(&"More text under heading".to_string())
+        );
     }
 
     #[test]
@@ -432,7 +1418,7 @@ This is *bold* and `inline_code` and a [link](https://example.com).
         let s = &sections[0];
 
         assert_eq!(s.title, "Title");
-        let body = &s.body_text;
+        let body = texts(s);
 
         // Our node_to_plain_text should keep the words, drop formatting
         assert!(body[0].contains(&"This is".to_string()), "{:?}", body);
@@ -442,7 +1428,7 @@ This is *bold* and `inline_code` and a [link](https://example.com).
     }
 
     #[test]
-    fn inline_code_is_captured_as_code_blocks() {
+    fn inline_code_is_captured_separately_from_code_blocks() {
         let src = r#"
 # Title
 
@@ -453,11 +1439,148 @@ Paragraph with `first` inline code and `second` snippet.
 
         assert_eq!(sections.len(), 1);
         let s = &sections[0];
+        assert!(s.code_blocks.is_empty());
+
+        let inline_values: Vec<&str> = s.inline_code.iter().map(|c| c.value.as_str()).collect();
+        assert_eq!(inline_values, vec!["first", "second"]);
+        assert_eq!(s.inline_code[0].start_line, Some(4));
+        assert_eq!(s.inline_code[0].start_column, Some(16));
+
+        // Still part of the prose by default.
+        assert!(s.body_text[0].text.contains("first inline code"));
+    }
+
+    #[test]
+    fn inline_code_can_be_stripped_from_prose() {
+        let src = "# Run `cargo`
+
+Call `x --help` now.
+
+- item `y`
+";
+        let options = IndexOptions {
+            strip_inline_code: true,
+            ..IndexOptions::default()
+        };
+
+        let sections = index_markdown_with_options(src, &options).expect("parse ok");
+        let s = &sections[0];
+
+        assert_eq!(s.title, "Run cargo");
+        assert_eq!(texts(s), vec!["Call now.", "item"]);
+        assert_eq!(s.blocks[0].text, "Call now.");
+        assert_eq!(s.lists[0].items[0].text, "item");
+
+        let inline_values: Vec<&str> = s.inline_code.iter().map(|c| c.value.as_str()).collect();
+        assert_eq!(inline_values, vec!["cargo", "x --help", "y"]);
+    }
+
+    #[test]
+    fn blocks_keep_prose_and_code_interleaved() {
+        let src = "# Demo\n\nExplain.\n\n```rust\nfn main() {}\n```\n\nOutput:\n\n- one\n- two\n\n> quoted\n\n---\n\n<br>\n\n[ref]: https://example.com\n";
+
+        let sections = index_markdown(src).expect("parse ok");
+        let blocks = &sections[0].blocks;
+
+        let kinds: Vec<BlockKind> = blocks.iter().map(|b| b.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                BlockKind::Paragraph,
+                BlockKind::Code,
+                BlockKind::Paragraph,
+                BlockKind::List,
+                BlockKind::Quote,
+                BlockKind::ThematicBreak,
+                BlockKind::Html,
+            ]
+        );
+        assert_eq!(blocks[0].text, "Explain.");
+        assert_eq!(blocks[1].text, "fn main() {}");
+        assert_eq!(blocks[1].lang.as_deref(), Some("rust"));
+        assert_eq!(
+            (blocks[1].start_line, blocks[1].end_line),
+            (Some(5), Some(7))
+        );
+        assert_eq!(blocks[3].text, "one\ntwo");
+        assert_eq!(blocks[6].text, "<br>");
+    }
+
+    #[test]
+    fn leading_html_comment_does_not_create_a_preamble() {
+        let src = "<!-- generated -->\n# Title\nText.\n";
+
+        let sections = index_markdown(src).expect("parse ok");
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].blocks.len(), 1);
+    }
+
+    #[test]
+    fn code_nested_in_lists_and_blockquotes_is_captured() {
+        let src = "# Steps\n\n1. Install:\n\n   ```bash {title=\"install\"}\n   cargo install x\n   ```\n\n2. Run `x --help`.\n\n> Note:\n>\n> ```toml\n> [x]\n> ```\n";
 
-        let inline_values: Vec<&str> = s.code_blocks.iter().map(|cb| cb.value.as_str()).collect();
+        let sections = index_markdown(src).expect("parse ok");
+        let blocks = &sections[0].code_blocks;
+        assert_eq!(blocks.len(), 2, "{:?}", blocks);
 
-        assert!(inline_values.contains(&"first"));
-        assert!(inline_values.contains(&"second"));
+        assert_eq!(blocks[0].lang.as_deref(), Some("bash"));
+        assert_eq!(blocks[0].meta.as_deref(), Some("{title=\"install\"}"));
+        assert_eq!(blocks[0].value, "cargo install x");
+        assert_eq!(blocks[0].start_line, Some(5));
+        assert_eq!(blocks[0].end_line, Some(7));
+
+        assert_eq!(blocks[1].lang.as_deref(), Some("toml"));
+        assert_eq!(blocks[1].value, "[x]");
+        assert_eq!(blocks[1].start_line, Some(13));
+
+        assert_eq!(sections[0].inline_code[0].value, "x --help");
+    }
+
+    #[test]
+    fn code_blocks_record_kind_and_meta_attributes() {
+        let src = "# Code\n\n```rust title=\"main.rs\" {3-5}\nfn main() {}\n```\n\n    indented\n\n> ~~~\n> tilde\n> ~~~\n\nSee `x`.\n";
+
+        let sections = index_markdown(src).expect("parse ok");
+        let blocks = &sections[0].code_blocks;
+        assert_eq!(blocks.len(), 3);
+
+        assert_eq!(blocks[0].kind, CodeBlockKind::Fenced);
+        assert_eq!(blocks[0].lang.as_deref(), Some("rust"));
+        assert_eq!(blocks[0].meta.as_deref(), Some("title=\"main.rs\" {3-5}"));
+        assert_eq!(blocks[0].attributes["title"], "main.rs");
+        assert_eq!(blocks[0].attributes["highlight"], "3-5");
+
+        assert_eq!(blocks[1].kind, CodeBlockKind::Indented);
+        assert_eq!(blocks[1].value, "indented");
+        assert!(blocks[1].attributes.is_empty());
+
+        assert_eq!(blocks[2].kind, CodeBlockKind::Fenced);
+        assert_eq!(blocks[2].value, "tilde");
+    }
+
+    #[test]
+    fn blockquote_with_only_code_still_contributes_its_code() {
+        let src = "# Example\n\n> ```sh\n> echo hi\n> ```\n";
+
+        let sections = index_markdown(src).expect("parse ok");
+        assert_eq!(sections[0].code_blocks.len(), 1);
+        assert_eq!(sections[0].code_blocks[0].value, "echo hi");
+        assert_eq!(sections[0].end_line, Some(5));
+    }
+
+    #[test]
+    fn inline_code_in_table_cells_is_captured() {
+        let src = "| Flag |\n| --- |\n| `--tree` |\n";
+        let options = IndexOptions {
+            gfm: true,
+            ..IndexOptions::default()
+        };
+
+        let sections = index_markdown_with_options(src, &options).expect("parse ok");
+        assert_eq!(sections[0].inline_code.len(), 1);
+        assert_eq!(sections[0].inline_code[0].value, "--tree");
+        assert_eq!(sections[0].inline_code[0].start_line, Some(3));
+        assert_eq!(sections[0].tables[0].rows[0][0], "--tree");
     }
 
     #[test]
@@ -481,6 +1604,146 @@ Paragraph with `first` inline code and `second` snippet.
         assert_eq!(next.end_line, Some(7));
     }
 
+    #[test]
+    fn section_and_code_columns_and_offsets_are_captured() {
+        let src = "# Intro\nSee `x`.\n  ```rust\n  fn main() {}\n  ```\n## Next\nTail text.\n";
+
+        let sections = index_markdown(src).expect("parse ok");
+
+        let intro = &sections[0];
+        assert_eq!(intro.start_column, Some(1));
+        assert_eq!(intro.start_offset, Some(0));
+        // Ends just past the closing fence.
+        assert_eq!(intro.end_column, Some(6));
+        assert_eq!(intro.end_offset, Some(src.find("## Next").unwrap() - 1));
+
+        let inline = &intro.inline_code[0];
+        assert_eq!(inline.value, "x");
+        assert_eq!((inline.start_column, inline.end_column), (Some(5), Some(8)));
+        assert_eq!(
+            &src[inline.start_offset.unwrap()..inline.end_offset.unwrap()],
+            "`x`"
+        );
+
+        let fenced = &intro.code_blocks[0];
+        assert_eq!(fenced.start_line, Some(3));
+        assert_eq!(fenced.start_column, Some(3));
+        assert!(
+            src[fenced.start_offset.unwrap()..fenced.end_offset.unwrap()].starts_with("```rust")
+        );
+
+        let next = &sections[1];
+        assert_eq!(next.start_offset, src.find("## Next"));
+        assert_eq!(next.end_offset, Some(src.len() - 1));
+    }
+
+    #[test]
+    fn text_blocks_record_their_source_range_and_kind() {
+        let src = "# Intro\nFirst paragraph.\n\n> Quoted\n> text.\n\nThird.\n";
+
+        let sections = index_markdown(src).expect("parse ok");
+        let body = &sections[0].body_text;
+        assert_eq!(body.len(), 3);
+
+        assert_eq!(body[0].kind, BlockKind::Paragraph);
+        assert_eq!((body[0].start_line, body[0].end_line), (Some(2), Some(2)));
+
+        assert_eq!(body[1].kind, BlockKind::Quote);
+        assert_eq!((body[1].start_line, body[1].end_line), (Some(4), Some(5)));
+        assert_eq!(
+            &src[body[1].start_offset.unwrap()..body[1].end_offset.unwrap()],
+            "> Quoted\n> text."
+        );
+
+        assert_eq!(body[2].text, "Third.");
+        assert_eq!(body[2].start_column, Some(1));
+        assert_eq!(body[2].end_column, Some(7));
+    }
+
+    #[test]
+    fn text_blocks_can_be_markdown_source_or_html() {
+        let src = "# Intro\nSee *this* [page][p] and `x`.\n\n<script>alert(1)</script>\n\n- [click](javascript:alert(1))\n\n[p]: https://example.com \"Example\"\n";
+
+        let markdown = IndexOptions {
+            text_format: TextFormat::Markdown,
+            ..IndexOptions::default()
+        };
+        let sections = index_markdown_with_options(src, &markdown).expect("parse ok");
+        assert_eq!(
+            texts(&sections[0]),
+            vec![
+                "See *this* [page][p] and `x`.",
+                "- [click](javascript:alert(1))"
+            ]
+        );
+
+        let html = IndexOptions {
+            text_format: TextFormat::Html,
+            ..IndexOptions::default()
+        };
+        let sections = index_markdown_with_options(src, &html).expect("parse ok");
+        assert_eq!(
+            texts(&sections[0]),
+            vec![
+                "<p>See <em>this</em> <a href=\"https://example.com\" title=\"Example\">page</a> and <code>x</code>.</p>",
+                "<ul>\n<li><a href=\"\">click</a></li>\n</ul>"
+            ]
+        );
+    }
+
+    #[test]
+    fn display_math_without_text_extends_the_section() {
+        let src = "# Euler\nIntro.\n\n$$\ne^{i\\pi} = -1\n$$\n";
+        let options = IndexOptions {
+            math: true,
+            ..IndexOptions::default()
+        };
+
+        let sections = index_markdown_with_options(src, &options).expect("parse ok");
+        let s = &sections[0];
+        assert_eq!(texts(s), vec!["Intro."]);
+        assert_eq!(s.math_blocks[0].end_line, Some(6));
+        assert_eq!(s.end_line, Some(6));
+        assert_eq!(s.end_offset, Some(src.len() - 1));
+    }
+
+    #[test]
+    fn math_is_recorded_apart_from_prose() {
+        let src = "# Euler\nSo $e^{i\\pi} = -1$ holds.\n\n$$\n\\int_0^1 x\\,dx\n$$\n";
+        let options = IndexOptions {
+            math: true,
+            ..IndexOptions::default()
+        };
+
+        let sections = index_markdown_with_options(src, &options).expect("parse ok");
+        let s = &sections[0];
+        assert_eq!(texts(s), vec!["So holds."]);
+
+        assert_eq!(s.inline_math.len(), 1);
+        assert_eq!(s.inline_math[0].value, "e^{i\\pi} = -1");
+        assert_eq!(s.inline_math[0].start_column, Some(4));
+
+        assert_eq!(s.math_blocks.len(), 1);
+        assert_eq!(s.math_blocks[0].value, "\\int_0^1 x\\,dx");
+        assert_eq!(
+            (s.math_blocks[0].start_line, s.math_blocks[0].end_line),
+            (Some(4), Some(6))
+        );
+        assert_eq!(s.blocks[1].kind, BlockKind::Math);
+
+        let keep = IndexOptions {
+            math_text: true,
+            ..options
+        };
+        let sections = index_markdown_with_options(src, &keep).expect("parse ok");
+        let s = &sections[0];
+        assert_eq!(
+            texts(s),
+            vec!["So e^{i\\pi} = -1 holds.", "\\int_0^1 x\\,dx"]
+        );
+        assert_eq!(s.body_text[1].kind, BlockKind::Math);
+    }
+
     #[test]
     fn preamble_section_has_no_heading_line_metadata() {
         let src = "Preamble text.\n# Heading\nUnder heading.\n";
@@ -495,29 +1758,277 @@ Paragraph with `first` inline code and `second` snippet.
         assert_eq!(preamble.heading_line, None);
     }
 
+    #[test]
+    fn gfm_tables_are_only_recognized_when_enabled() {
+        let src = "# Options\n| name | default |\n| --- | --- |\n| depth | none |\n";
+
+        let plain = index_markdown(src).expect("parse ok");
+        assert!(plain[0].body_text[0].text.contains('|'));
+
+        let options = IndexOptions {
+            gfm: true,
+            ..IndexOptions::default()
+        };
+        let gfm = index_markdown_with_options(src, &options).expect("parse ok");
+        assert!(
+            !gfm[0].body_text[0].text.contains('|'),
+            "{:?}",
+            gfm[0].body_text
+        );
+        assert!(gfm[0].body_text[0].text.contains("depth"));
+    }
+
+    #[test]
+    fn gfm_tables_are_captured_as_rows_and_columns() {
+        let src = "# Options\n\n| Option | Default | Description |\n| :--- | :---: | --- |\n| `--depth` | none | Max depth |\n| `--tree` | off | Nest sections |\n";
+        let options = IndexOptions {
+            gfm: true,
+            ..IndexOptions::default()
+        };
+
+        let sections = index_markdown_with_options(src, &options).expect("parse ok");
+        assert_eq!(sections[0].tables.len(), 1);
+
+        let table = &sections[0].tables[0];
+        assert_eq!(
+            table.align,
+            vec![ColumnAlign::Left, ColumnAlign::Center, ColumnAlign::None]
+        );
+        assert_eq!(table.header, vec!["Option", "Default", "Description"]);
+        assert_eq!(
+            table.rows,
+            vec![
+                vec!["--depth", "none", "Max depth"],
+                vec!["--tree", "off", "Nest sections"],
+            ]
+        );
+        assert_eq!(table.start_line, Some(3));
+        assert_eq!(table.end_line, Some(6));
+        assert_eq!(sections[0].end_line, Some(6));
+    }
+
+    #[test]
+    fn lists_keep_items_separate_and_nested() {
+        let src = "# Steps\n\n3. Step one\n4. Step two\n   - detail a\n   - detail b\n";
+
+        let sections = index_markdown(src).expect("parse ok");
+        let s = &sections[0];
+
+        assert_eq!(
+            texts(s),
+            vec!["Step one\nStep two\n  detail a\n  detail b".to_string()]
+        );
+        assert_eq!(s.lists.len(), 1);
+
+        let list = &s.lists[0];
+        assert!(list.ordered);
+        assert_eq!(list.start, Some(3));
+        assert_eq!(list.start_line, Some(3));
+        assert_eq!(list.end_line, Some(6));
+        assert_eq!(list.items.len(), 2);
+        assert_eq!(list.items[0].text, "Step one");
+        assert!(list.items[0].children.is_empty());
+
+        let nested = &list.items[1].children[0];
+        assert!(!nested.ordered);
+        assert_eq!(nested.start, None);
+        assert_eq!(nested.items[1].text, "detail b");
+        assert_eq!(nested.items[1].start_line, Some(6));
+    }
+
+    #[test]
+    fn gfm_task_list_items_record_checked_state() {
+        let src = "- [x] done\n- [ ] todo\n- plain\n";
+        let options = IndexOptions {
+            gfm: true,
+            ..IndexOptions::default()
+        };
+
+        let sections = index_markdown_with_options(src, &options).expect("parse ok");
+        let items = &sections[0].lists[0].items;
+
+        assert_eq!(items[0].checked, Some(true));
+        assert_eq!(items[0].text, "done");
+        assert_eq!(items[1].checked, Some(false));
+        assert_eq!(items[2].checked, None);
+    }
+
+    #[test]
+    fn frontmatter_is_not_mistaken_for_a_heading_when_enabled() {
+        let src = "---\ntitle: Post\n---\n# Heading\n";
+
+        let plain = index_markdown(src).expect("parse ok");
+        assert_eq!(plain[0].title, "title: Post");
+
+        let options = IndexOptions {
+            frontmatter: true,
+            ..IndexOptions::default()
+        };
+        let sections = index_markdown_with_options(src, &options).expect("parse ok");
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].title, "Heading");
+    }
+
+    #[test]
+    fn yaml_frontmatter_becomes_metadata_not_text() {
+        let src = "---\ntitle: Post\ntags: [rust, docs]\ndate: 2024-01-02\ndraft: false\n---\nIntro.\n# Heading\n";
+        let options = IndexOptions {
+            frontmatter: true,
+            ..IndexOptions::default()
+        };
+
+        let doc = index_document(src, &options).expect("parse ok");
+        assert_eq!(
+            doc.metadata,
+            Some(json!({
+                "title": "Post",
+                "tags": ["rust", "docs"],
+                "date": "2024-01-02",
+                "draft": false
+            }))
+        );
+        assert_eq!(doc.sections[0].title, "(preamble)");
+        assert_eq!(texts(&doc.sections[0]), vec!["Intro."]);
+    }
+
+    #[test]
+    fn toml_frontmatter_becomes_metadata() {
+        let src = "+++\ntitle = \"Post\"\ndate = 2024-01-02\nweight = 3\n+++\n# Heading\n";
+        let options = IndexOptions {
+            frontmatter: true,
+            ..IndexOptions::default()
+        };
+
+        let doc = index_document(src, &options).expect("parse ok");
+        assert_eq!(
+            doc.metadata,
+            Some(json!({ "title": "Post", "date": "2024-01-02", "weight": 3 }))
+        );
+        assert_eq!(doc.sections.len(), 1);
+    }
+
+    #[test]
+    fn invalid_frontmatter_yields_no_metadata() {
+        let src = "---\ntitle: [unclosed\n---\n# Heading\n";
+        let options = IndexOptions {
+            frontmatter: true,
+            ..IndexOptions::default()
+        };
+
+        let doc = index_document(src, &options).expect("parse ok");
+        assert_eq!(doc.metadata, None);
+        assert_eq!(doc.sections.len(), 1);
+    }
+
+    #[test]
+    fn math_and_mdx_constructs_follow_options() {
+        let options = IndexOptions {
+            mdx: true,
+            math: true,
+            ..IndexOptions::default()
+        };
+        let parse = options.parse_options();
+
+        assert!(parse.constructs.math_flow);
+        assert!(parse.constructs.math_text);
+        assert!(parse.constructs.mdx_jsx_flow);
+        assert!(!parse.constructs.html_flow);
+        assert!(!parse.constructs.gfm_table);
+    }
+
+    #[test]
+    fn headings_get_unique_anchors_and_honor_custom_ids() {
+        let src = "Preamble.\n# Usage\n## Usage\n## Install {#setup}\n## Setup\n";
+
+        let sections = index_markdown(src).expect("parse ok");
+        let anchors: Vec<Option<&str>> = sections.iter().map(|s| s.anchor.as_deref()).collect();
+
+        assert_eq!(
+            anchors,
+            vec![
+                None,
+                Some("usage"),
+                Some("usage-1"),
+                Some("setup"),
+                Some("setup-1")
+            ]
+        );
+        assert_eq!(sections[3].title, "Install");
+    }
+
+    #[test]
+    fn custom_ids_are_reserved_before_earlier_headings_are_slugged() {
+        let src = "# Setup
+# Other {#setup}
+";
+
+        let sections = index_markdown(src).expect("parse ok");
+        let anchors: Vec<Option<&str>> = sections.iter().map(|s| s.anchor.as_deref()).collect();
+
+        assert_eq!(anchors, vec![Some("setup-1"), Some("setup")]);
+    }
+
     #[test]
     fn json_document_element_serializes_code_block_line_metadata() {
         let doc = JsonDocumentElement {
+            id: 0,
+            parent_id: None,
             file_path: "doc.md".to_string(),
             header: "Intro".to_string(),
-            text_blocks: vec!["Body".to_string()],
+            level: 1,
+            anchor: Some("intro".to_string()),
+            breadcrumbs: Vec::new(),
+            text_blocks: JsonTextBlocks::Plain(vec!["Body".to_string()]),
             code_blocks: vec![JsonCodeBlock {
+                kind: CodeBlockKind::Fenced,
+                lang: Some("rust".to_string()),
+                meta: Some("title=\"main.rs\"".to_string()),
+                attributes: BTreeMap::from([("title".to_string(), "main.rs".to_string())]),
                 value: "println!(\"hi\");".to_string(),
                 start_line: Some(10),
                 end_line: Some(12),
+                start_column: Some(1),
+                end_column: Some(4),
+                start_offset: Some(120),
+                end_offset: Some(150),
             }],
+            inline_code: Vec::new(),
+            math_blocks: Vec::new(),
+            inline_math: Vec::new(),
+            tables: Vec::new(),
+            lists: Vec::new(),
+            links: Vec::new(),
+            images: Vec::new(),
+            callouts: Vec::new(),
+            footnotes: Vec::new(),
+            details: Vec::new(),
+            raw_html: None,
+            blocks: None,
             start_line: Some(1),
             end_line: Some(12),
+            start_column: Some(1),
+            end_column: Some(4),
+            start_offset: Some(0),
+            end_offset: Some(150),
             heading_line: Some(1),
+            metadata: None,
         };
 
         let value = serde_json::to_value(&doc).expect("serialize");
         assert_eq!(
             value["code_blocks"][0],
             json!({
+                "kind": "fenced",
+                "lang": "rust",
+                "meta": "title=\"main.rs\"",
+                "attributes": { "title": "main.rs" },
                 "value": "println!(\"hi\");",
                 "start_line": 10,
-                "end_line": 12
+                "end_line": 12,
+                "start_column": 1,
+                "end_column": 4,
+                "start_offset": 120,
+                "end_offset": 150
             })
         );
     }
@@ -0,0 +1,283 @@
+use crate::{node_start_line, node_to_plain_text};
+use markdown::mdast;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Where a link points, judged from its destination alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkKind {
+    /// A relative or absolute path, possibly with a `#fragment`.
+    Internal,
+    /// A URL with a scheme (`https:`, `mailto:`, …) or a `//host` prefix.
+    External,
+    /// A bare `#fragment` within the same document.
+    Anchor,
+}
+
+impl LinkKind {
+    pub fn classify(destination: &str) -> Self {
+        if destination.starts_with('#') {
+            LinkKind::Anchor
+        } else if destination.starts_with("//") || has_scheme(destination) {
+            LinkKind::External
+        } else {
+            LinkKind::Internal
+        }
+    }
+}
+
+/// `scheme:` as defined by RFC 3986: a letter followed by letters, digits,
+/// `+`, `-` or `.`.
+fn has_scheme(destination: &str) -> bool {
+    let Some((scheme, _)) = destination.split_once(':') else {
+        return false;
+    };
+    let mut chars = scheme.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+/// An outgoing link. Reference-style links are resolved through their
+/// definitions, so `destination` is always the final URL.
+#[derive(Debug, Clone)]
+pub struct Link {
+    pub text: String,
+    pub destination: String,
+    pub title: Option<String>,
+    pub kind: LinkKind,
+    pub line: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct JsonLink {
+    pub text: String,
+    pub destination: String,
+    pub title: Option<String>,
+    pub kind: LinkKind,
+    pub line: Option<usize>,
+}
+
+impl From<Link> for JsonLink {
+    fn from(link: Link) -> Self {
+        JsonLink {
+            text: link.text,
+            destination: link.destination,
+            title: link.title,
+            kind: link.kind,
+            line: link.line,
+        }
+    }
+}
+
+/// An image. Reference-style images are resolved through their definitions,
+/// like links.
+#[derive(Debug, Clone)]
+pub struct Image {
+    pub alt: String,
+    pub url: String,
+    pub title: Option<String>,
+    pub line: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct JsonImage {
+    pub alt: String,
+    pub url: String,
+    pub title: Option<String>,
+    pub line: Option<usize>,
+}
+
+impl From<Image> for JsonImage {
+    fn from(image: Image) -> Self {
+        JsonImage {
+            alt: image.alt,
+            url: image.url,
+            title: image.title,
+            line: image.line,
+        }
+    }
+}
+
+/// Link reference definitions (`[id]: url "title"`), keyed by identifier.
+pub(crate) type Definitions = HashMap<String, (String, Option<String>)>;
+
+/// Gather every definition in the document, wherever it is nested.
+pub(crate) fn collect_definitions(node: &mdast::Node, out: &mut Definitions) {
+    if let mdast::Node::Definition(def) = node {
+        // The first definition of an identifier wins, as in CommonMark.
+        out.entry(def.identifier.clone())
+            .or_insert_with(|| (def.url.clone(), def.title.clone()));
+    }
+
+    if let Some(children) = node.children() {
+        for child in children {
+            collect_definitions(child, out);
+        }
+    }
+}
+
+/// Push every link found in `node` or its descendants.
+pub(crate) fn collect_links(node: &mdast::Node, definitions: &Definitions, out: &mut Vec<Link>) {
+    match node {
+        mdast::Node::Link(link) => out.push(Link {
+            text: node_to_plain_text(node),
+            destination: link.url.clone(),
+            title: link.title.clone(),
+            kind: LinkKind::classify(&link.url),
+            line: node_start_line(node),
+        }),
+        mdast::Node::LinkReference(reference) => {
+            if let Some((url, title)) = definitions.get(&reference.identifier) {
+                out.push(Link {
+                    text: node_to_plain_text(node),
+                    destination: url.clone(),
+                    title: title.clone(),
+                    kind: LinkKind::classify(url),
+                    line: node_start_line(node),
+                });
+            }
+        }
+        _ => {
+            if let Some(children) = node.children() {
+                for child in children {
+                    collect_links(child, definitions, out);
+                }
+            }
+        }
+    }
+}
+
+/// Push every image found in `node` or its descendants.
+pub(crate) fn collect_images(node: &mdast::Node, definitions: &Definitions, out: &mut Vec<Image>) {
+    match node {
+        mdast::Node::Image(image) => out.push(Image {
+            alt: image.alt.clone(),
+            url: image.url.clone(),
+            title: image.title.clone(),
+            line: node_start_line(node),
+        }),
+        mdast::Node::ImageReference(reference) => {
+            if let Some((url, title)) = definitions.get(&reference.identifier) {
+                out.push(Image {
+                    alt: reference.alt.clone(),
+                    url: url.clone(),
+                    title: title.clone(),
+                    line: node_start_line(node),
+                });
+            }
+        }
+        _ => {
+            if let Some(children) = node.children() {
+                for child in children {
+                    collect_images(child, definitions, out);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{IndexOptions, index_markdown, index_markdown_with_options};
+
+    #[test]
+    fn destinations_are_classified() {
+        assert_eq!(LinkKind::classify("#usage"), LinkKind::Anchor);
+        assert_eq!(LinkKind::classify("guide.md#usage"), LinkKind::Internal);
+        assert_eq!(LinkKind::classify("../img/a.png"), LinkKind::Internal);
+        assert_eq!(LinkKind::classify("/docs/a.md"), LinkKind::Internal);
+        assert_eq!(
+            LinkKind::classify("https://example.com"),
+            LinkKind::External
+        );
+        assert_eq!(
+            LinkKind::classify("mailto:me@example.com"),
+            LinkKind::External
+        );
+        assert_eq!(
+            LinkKind::classify("//cdn.example.com/x.js"),
+            LinkKind::External
+        );
+        assert_eq!(LinkKind::classify("c:thing"), LinkKind::External);
+        assert_eq!(LinkKind::classify("1:thing"), LinkKind::Internal);
+    }
+
+    #[test]
+    fn inline_and_reference_links_are_recorded_per_section() {
+        let src = "# Intro\nSee [the guide](guide.md \"Guide\") and [home][site].\n\n## Next\n- [Usage](#usage)\n\n[site]: https://example.com\n";
+
+        let sections = index_markdown(src).expect("parse ok");
+        let intro = &sections[0].links;
+        assert_eq!(intro.len(), 2);
+
+        assert_eq!(intro[0].text, "the guide");
+        assert_eq!(intro[0].destination, "guide.md");
+        assert_eq!(intro[0].title.as_deref(), Some("Guide"));
+        assert_eq!(intro[0].kind, LinkKind::Internal);
+        assert_eq!(intro[0].line, Some(2));
+
+        assert_eq!(intro[1].text, "home");
+        assert_eq!(intro[1].destination, "https://example.com");
+        assert_eq!(intro[1].kind, LinkKind::External);
+
+        let next = &sections[1].links;
+        assert_eq!(next.len(), 1);
+        assert_eq!(next[0].destination, "#usage");
+        assert_eq!(next[0].kind, LinkKind::Anchor);
+        assert_eq!(next[0].line, Some(5));
+    }
+
+    #[test]
+    fn images_are_recorded_and_their_alt_text_is_searchable() {
+        let src = "# Architecture\n[![Build status](badge.svg)](ci.md)\n\n![System *diagram*][arch] and ![missing][nope]\n\n[arch]: img/arch.png \"Overview\"\n";
+
+        let sections = index_markdown(src).expect("parse ok");
+        let images = &sections[0].images;
+        assert_eq!(images.len(), 2);
+
+        assert_eq!(images[0].alt, "Build status");
+        assert_eq!(images[0].url, "badge.svg");
+        assert_eq!(images[0].title, None);
+        assert_eq!(images[0].line, Some(2));
+
+        assert_eq!(images[1].alt, "System diagram");
+        assert_eq!(images[1].url, "img/arch.png");
+        assert_eq!(images[1].title.as_deref(), Some("Overview"));
+        assert_eq!(images[1].line, Some(4));
+
+        assert_eq!(sections[0].links[0].text, "Build status");
+        assert_eq!(
+            sections[0].body_text[1].text,
+            "System diagram and ![missing][nope]"
+        );
+    }
+
+    #[test]
+    fn an_image_without_alt_text_extends_the_section() {
+        let src = "# Diagram\nIntro.\n\n![](arch.png)\n";
+
+        let sections = index_markdown(src).expect("parse ok");
+        assert_eq!(sections[0].body_text.len(), 1);
+        assert_eq!(sections[0].images[0].line, Some(4));
+        assert_eq!(sections[0].end_line, Some(4));
+    }
+
+    #[test]
+    fn links_in_headings_and_tables_are_recorded() {
+        let src = "# See [API](api.md)\n\n| Page |\n| --- |\n| [FAQ](faq.md) |\n";
+        let options = IndexOptions {
+            gfm: true,
+            ..IndexOptions::default()
+        };
+
+        let sections = index_markdown_with_options(src, &options).expect("parse ok");
+        let destinations: Vec<&str> = sections[0]
+            .links
+            .iter()
+            .map(|l| l.destination.as_str())
+            .collect();
+        assert_eq!(destinations, vec!["api.md", "faq.md"]);
+    }
+}
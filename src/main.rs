@@ -1,14 +1,28 @@
 // file name: main.rs
-use markdown2json::{JsonCodeBlock, JsonDocumentElement, index_markdown};
-use serde_json;
+mod check_links;
+mod sort;
+
+use ignore::{
+    WalkBuilder,
+    gitignore::{Gitignore, GitignoreBuilder},
+    overrides::{Override, OverrideBuilder},
+};
+use markdown2json::{
+    Document, IndexOptions, JsonBlock, JsonCallout, JsonCodeBlock, JsonDetails,
+    JsonDocumentElement, JsonFootnote, JsonImage, JsonInlineCode, JsonLink, JsonList, JsonMath,
+    JsonRawHtml, JsonSectionNode, JsonTable, JsonTextBlocks, SectionNode, SectionTree,
+    index_document,
+};
+use sort::SortOrder;
 use std::{
+    collections::HashSet,
     env, fs, io,
     path::{Path, PathBuf},
 };
 
-fn main() -> Result<(), markdown::message::Message> {
+fn main() {
     let args: Vec<String> = env::args().collect();
-    let (inputs, max_depth) = match parse_args(&args) {
+    let cli = match parse_args(&args) {
         Ok(parsed) => parsed,
         Err(msg) => {
             eprintln!("{}", msg);
@@ -19,7 +33,7 @@ fn main() -> Result<(), markdown::message::Message> {
     let mut existing_inputs: Vec<PathBuf> = Vec::new();
     let mut missing_inputs: Vec<String> = Vec::new();
 
-    for input in &inputs {
+    for input in &cli.inputs {
         let path = PathBuf::from(input);
         if path.exists() {
             existing_inputs.push(path);
@@ -36,161 +50,632 @@ fn main() -> Result<(), markdown::message::Message> {
         std::process::exit(1);
     }
 
+    if cli.command == Command::CheckLinks {
+        let mut files = Vec::new();
+        let mut seen = SeenFiles::new();
+        for path in &existing_inputs {
+            collect_markdown_files(path, &mut files, &mut seen, 0, &cli);
+        }
+
+        let report = check_links::check_links(&files, &cli.index);
+        for diagnostic in &report.diagnostics {
+            println!("{}", diagnostic);
+        }
+        eprintln!(
+            "Checked {} links in {} files: {} broken",
+            report.links_checked,
+            files.len(),
+            report.diagnostics.len()
+        );
+        if !report.diagnostics.is_empty() {
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let mut all_docs: Vec<JsonDocumentElement> = Vec::new();
+    // Shared by all inputs, so a file named twice is indexed once.
+    let mut seen = SeenFiles::new();
 
     for path in &existing_inputs {
         // pass starting depth = 0
-        process_path(path, &mut all_docs, 0, max_depth)?;
+        process_path(path, &mut all_docs, &mut seen, 0, &cli);
     }
 
-    serde_json::to_writer_pretty(io::stdout(), &all_docs).expect("failed to serialize JSON");
+    if cli.tree {
+        let all_trees = JsonSectionNode::nest(all_docs);
+        serde_json::to_writer_pretty(io::stdout(), &all_trees).expect("failed to serialize JSON");
+    } else {
+        serde_json::to_writer_pretty(io::stdout(), &all_docs).expect("failed to serialize JSON");
+    }
     println!();
-    Ok(())
 }
 
-/// Parse CLI arguments into a list of input paths and an optional depth limit.
+/// What to do with the indexed files.
+#[derive(Debug, Default, PartialEq)]
+enum Command {
+    /// Emit the JSON index (the default).
+    #[default]
+    Index,
+    /// Report broken relative links and anchors instead of emitting JSON.
+    CheckLinks,
+}
+
+/// Parsed command line: an optional subcommand, positional inputs, then
+/// optional flags.
+#[derive(Debug, Default, PartialEq)]
+struct CliArgs {
+    command: Command,
+    inputs: Vec<String>,
+    max_depth: Option<usize>,
+    /// Globs a file has to match to be indexed, if any are given.
+    include: Vec<String>,
+    /// Globs for files and directories to skip.
+    exclude: Vec<String>,
+    /// Descend into dot-directories and index dot-files.
+    hidden: bool,
+    /// Descend into symlinked directories.
+    follow_symlinks: bool,
+    /// Order of the files within each input.
+    sort: SortOrder,
+    tree: bool,
+    blocks: bool,
+    /// Emit `text_blocks` as bare strings, as before they had positions.
+    legacy_text_blocks: bool,
+    raw_html: bool,
+    index: IndexOptions,
+}
+
+/// Flags that only shape the JSON output, so `check-links` has no use for them.
+const OUTPUT_FLAGS: [&str; 10] = [
+    "--tree",
+    "--blocks",
+    "--legacy-text-blocks",
+    "--raw-html",
+    "--strip-inline-code",
+    "--link-urls",
+    "--no-image-alt-text",
+    "--footnote-markers",
+    "--math-text",
+    "--text-format",
+];
+
+/// Parse CLI arguments into a list of input paths and the trailing flags.
 ///
 /// Expectations and validation rules:
+/// - `check-links` as the first argument selects link checking instead of indexing.
 /// - At least one positional input is required; the program name is at `args[0]`.
-/// - The optional `--depth`/`-d` flag (with its numeric value) must appear **after**
-///   all positional inputs. Any flag-like token before the final position triggers
+/// - Flags (`--depth`/`-d N`, `--tree`, `--gfm`, …) must appear **after** all positional
+///   inputs. A flag before the first input, or an input after a flag, triggers
 ///   an error so we can clearly tell users about ordering requirements.
 /// - If the depth flag is provided without a following value, the function returns
 ///   a helpful error message rather than panicking.
-fn parse_args(args: &[String]) -> Result<(Vec<String>, Option<usize>), String> {
+/// - `check-links` rejects the flags in [`OUTPUT_FLAGS`], which it would ignore.
+fn parse_args(args: &[String]) -> Result<CliArgs, String> {
     if args.len() < 2 {
         return Err(usage(&args[0]));
     }
 
-    let mut depth: Option<usize> = None;
-    let mut inputs_slice: &[String] = &args[1..];
-
-    if let Some(last) = inputs_slice.last() {
-        if last == "--depth" || last == "-d" {
-            return Err("Expected a value after --depth/-d".to_string());
-        }
-    }
-
-    if inputs_slice.len() >= 2 {
-        let flag_candidate = &inputs_slice[inputs_slice.len() - 2];
-        let value_candidate = &inputs_slice[inputs_slice.len() - 1];
+    let (command, rest) = match args[1].as_str() {
+        "check-links" => (Command::CheckLinks, &args[2..]),
+        _ => (Command::Index, &args[1..]),
+    };
+    let flags_start = rest
+        .iter()
+        .position(|arg| arg.starts_with('-'))
+        .unwrap_or(rest.len());
+    let (inputs, flags) = rest.split_at(flags_start);
 
-        if flag_candidate == "--depth" || flag_candidate == "-d" {
-            depth = Some(
-                value_candidate
-                    .parse::<usize>()
-                    .map_err(|_| format!("Invalid depth value: {}", value_candidate))?,
-            );
-            inputs_slice = &inputs_slice[..inputs_slice.len() - 2];
-        }
+    if inputs.is_empty() {
+        return Err(match flags.first() {
+            Some(flag) => misplaced_flag(&args[0], flag),
+            None => usage(&args[0]),
+        });
     }
 
-    if inputs_slice.is_empty() {
-        return Err(usage(&args[0]));
-    }
+    let mut cli = CliArgs {
+        command,
+        inputs: inputs.to_vec(),
+        ..CliArgs::default()
+    };
 
-    for arg in inputs_slice {
-        if arg.starts_with('-') {
+    let mut i = 0;
+    while i < flags.len() {
+        let flag = &flags[i];
+        if cli.command == Command::CheckLinks && OUTPUT_FLAGS.contains(&flag.as_str()) {
             return Err(format!(
-                "Unknown flag or flag placed before inputs: {}\n{}",
-                arg,
+                "{} only changes the JSON output and cannot be used with check-links\n{}",
+                flag,
                 usage(&args[0])
             ));
         }
+        match flag.as_str() {
+            "--depth" | "-d" => {
+                let value = flags
+                    .get(i + 1)
+                    .ok_or_else(|| "Expected a value after --depth/-d".to_string())?;
+                cli.max_depth = Some(
+                    value
+                        .parse::<usize>()
+                        .map_err(|_| format!("Invalid depth value: {}", value))?,
+                );
+                i += 2;
+            }
+            "--include" | "--exclude" => {
+                let value = flags
+                    .get(i + 1)
+                    .ok_or_else(|| format!("Expected a glob after {}", flag))?;
+                if flag == "--include" {
+                    cli.include.push(value.clone());
+                } else {
+                    cli.exclude.push(value.clone());
+                }
+                i += 2;
+            }
+            "--hidden" => {
+                cli.hidden = true;
+                i += 1;
+            }
+            "--follow-symlinks" => {
+                cli.follow_symlinks = true;
+                i += 1;
+            }
+            "--sort" => {
+                let value = flags
+                    .get(i + 1)
+                    .ok_or_else(|| "Expected a value after --sort".to_string())?;
+                cli.sort = value.parse()?;
+                i += 2;
+            }
+            "--tree" => {
+                cli.tree = true;
+                i += 1;
+            }
+            "--blocks" => {
+                cli.blocks = true;
+                i += 1;
+            }
+            "--legacy-text-blocks" => {
+                cli.legacy_text_blocks = true;
+                i += 1;
+            }
+            "--raw-html" => {
+                cli.raw_html = true;
+                i += 1;
+            }
+            "--gfm" => {
+                cli.index.gfm = true;
+                i += 1;
+            }
+            "--mdx" => {
+                cli.index.mdx = true;
+                i += 1;
+            }
+            "--math" => {
+                cli.index.math = true;
+                i += 1;
+            }
+            "--frontmatter" => {
+                cli.index.frontmatter = true;
+                i += 1;
+            }
+            "--strip-inline-code" => {
+                cli.index.strip_inline_code = true;
+                i += 1;
+            }
+            "--link-urls" => {
+                cli.index.link_urls = true;
+                i += 1;
+            }
+            "--no-image-alt-text" => {
+                cli.index.image_alt_text = false;
+                i += 1;
+            }
+            "--footnote-markers" => {
+                cli.index.footnote_markers = true;
+                i += 1;
+            }
+            "--math-text" => {
+                cli.index.math_text = true;
+                i += 1;
+            }
+            "--text-format" => {
+                let value = flags
+                    .get(i + 1)
+                    .ok_or_else(|| "Expected a value after --text-format".to_string())?;
+                cli.index.text_format = value.parse()?;
+                i += 2;
+            }
+            // An input after the flags started, or a flag we do not know.
+            _ => {
+                return Err(misplaced_flag(
+                    &args[0],
+                    if flag.starts_with('-') {
+                        flag
+                    } else {
+                        &flags[0]
+                    },
+                ));
+            }
+        }
     }
 
-    Ok((inputs_slice.to_vec(), depth))
+    PathFilters::new(Path::new("."), &cli.include, &cli.exclude)
+        .map_err(|err| format!("Invalid glob pattern: {}", err))?;
+
+    Ok(cli)
+}
+
+fn misplaced_flag(program: &str, flag: &str) -> String {
+    format!(
+        "Unknown flag or flag placed before inputs: {}\n{}",
+        flag,
+        usage(program)
+    )
 }
 
 fn usage(program: &str) -> String {
     format!(
-        "Usage: {program} <input1> [input2 ...] [--depth N]\n  • Each input can be a markdown file or a folder.\n  • The optional --depth/-d flag must come after all inputs."
+        "Usage: {program} [check-links] <input1> [input2 ...] [--depth N] [--include GLOB] [--exclude GLOB] [--hidden] [--follow-symlinks] [--sort path|mtime|title] [--tree] [--blocks] [--legacy-text-blocks] [--raw-html] [--gfm] [--mdx] [--math] [--frontmatter] [--strip-inline-code] [--link-urls] [--no-image-alt-text] [--footnote-markers] [--math-text] [--text-format plain|markdown|html]\n  • check-links reports broken relative links and anchors instead of emitting JSON; it takes the walk and extension flags, not the output ones.\n  • Each input can be a markdown file or a folder.\n  • Flags must come after all inputs.\n  • Directories are walked honoring .gitignore, .ignore and .markdown2jsonignore files.\n  • --include and --exclude filter the walk by gitignore-style globs; both can be repeated.\n  • --hidden also walks dot-directories and dot-files.\n  • --follow-symlinks descends into symlinked directories, skipping loops with a warning.\n  • --sort orders the files of each input by natural path (default), modification time or title.\n  • --tree nests subsections under their parent heading.\n  • --blocks adds each section's content as ordered, typed blocks.\n  • --legacy-text-blocks emits text_blocks as plain strings without positions.\n  • --raw-html adds the HTML blocks of each section as written.\n  • --gfm, --mdx, --math and --frontmatter enable those markdown extensions.\n  • --strip-inline-code leaves code spans out of text blocks; they stay in inline_code.\n  • --link-urls and --footnote-markers keep link URLs and footnote markers in text blocks.\n  • --no-image-alt-text leaves image alt text out of text blocks; it stays in images.\n  • --math-text keeps the LaTeX of math in text blocks; it is always listed in math_blocks and inline_math.\n  • --text-format emits text blocks as plain text (default), their markdown source or HTML."
     )
 }
 
+/// Index every markdown file under `path` into flat document elements.
+///
+/// Each element's `id` is its position in `docs`, and `parent_id` points at
+/// the element of the enclosing section in the same file.
+///
+/// `seen`: files already indexed, from this or earlier inputs
 /// `current_depth`: which level of recursion we are in (root = 0)
-/// `max_depth`: Some(N) means N is maximum allowed depth, None means infinite
+/// `cli`: depth limit, markdown extensions and output options
 fn process_path(
     path: &Path,
     docs: &mut Vec<JsonDocumentElement>,
+    seen: &mut SeenFiles,
     current_depth: usize,
-    max_depth: Option<usize>,
-) -> Result<(), markdown::message::Message> {
-    // If a max depth is defined and we are past it, stop recursion
-    if let Some(limit) = max_depth {
-        if current_depth > limit {
-            return Ok(());
+    cli: &CliArgs,
+) {
+    let mut files = Vec::new();
+    collect_markdown_files(path, &mut files, seen, current_depth, cli);
+
+    let mut documents = Vec::new();
+    for file in files {
+        // A file that does not parse, which MDX makes likely, is skipped so
+        // the rest of the corpus is still indexed.
+        match index_document(&read_markdown(&file), &cli.index) {
+            Ok(document) => documents.push((file, document)),
+            Err(message) => eprintln!(
+                "Warning: skipping {}",
+                check_links::Diagnostic::parse_error(&file, &message)
+            ),
         }
     }
+    if cli.sort == SortOrder::Title {
+        // Stable, so files with the same title stay in path order.
+        documents.sort_by_cached_key(|(file, document)| TitleKey(document_title(file, document)));
+    }
 
-    if path.is_dir() {
-        let entries = fs::read_dir(path).unwrap_or_else(|err| {
-            eprintln!("Failed to read directory {}: {}", path.display(), err);
-            std::process::exit(1);
-        });
+    for (file, document) in documents {
+        let tree = SectionTree::from_sections(document.sections);
+        let file_path = file.to_string_lossy().to_string();
+        let first_id = docs.len();
+
+        docs.extend(tree.nodes.into_iter().enumerate().map(|(index, node)| {
+            to_json_element(
+                &file_path,
+                first_id + index,
+                first_id,
+                node,
+                document.metadata.clone(),
+                cli,
+            )
+        }));
+    }
+}
+
+/// Name of the project-specific ignore file, read like `.gitignore`.
+const IGNORE_FILE_NAME: &str = ".markdown2jsonignore";
 
-        for entry in entries {
-            let entry = entry.unwrap_or_else(|err| {
+/// Walk `path` and push every markdown file found within the depth limit.
+///
+/// Entries excluded by `.gitignore`, `.ignore` or `.markdown2jsonignore`
+/// files are skipped, as are hidden ones unless `--hidden` is given. The
+/// `--include`/`--exclude` globs narrow the walk further.
+///
+/// Symlinked directories are only descended into with `--follow-symlinks`;
+/// loops are then skipped with a warning. A file reached through several
+/// paths is pushed once, under the first of them; files already in `seen`,
+/// from an earlier input, are not pushed again.
+fn collect_markdown_files(
+    path: &Path,
+    files: &mut Vec<PathBuf>,
+    seen: &mut SeenFiles,
+    current_depth: usize,
+    cli: &CliArgs,
+) {
+    // If a max depth is defined and we are past it, stop recursion
+    let max_depth = match cli.max_depth {
+        Some(limit) if current_depth > limit => return,
+        Some(limit) => Some(limit - current_depth),
+        None => None,
+    };
+
+    let filters = PathFilters::new(path, &cli.include, &cli.exclude).unwrap_or_else(|err| {
+        eprintln!("Invalid glob pattern: {}", err);
+        std::process::exit(1);
+    });
+
+    let walker = WalkBuilder::new(path)
+        .max_depth(max_depth)
+        .hidden(!cli.hidden)
+        // Ignore files apply whether or not the tree is a git checkout.
+        .require_git(false)
+        .add_custom_ignore_filename(IGNORE_FILE_NAME)
+        .overrides(filters.exclude.clone())
+        .follow_links(cli.follow_symlinks)
+        // Sorted siblings make the walk, and so the output, reproducible.
+        .sort_by_file_name(sort::compare_names)
+        .build();
+    let first = files.len();
+
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) => {
+                if let Some((ancestor, child)) = symlink_loop(&err) {
+                    eprintln!(
+                        "Warning: skipping symlink loop {} -> {}",
+                        child.display(),
+                        ancestor.display()
+                    );
+                    continue;
+                }
                 eprintln!(
                     "Failed to read directory entry in {}: {}",
                     path.display(),
                     err
                 );
                 std::process::exit(1);
-            });
+            }
+        };
 
-            let child_path = entry.path();
-            process_path(&child_path, docs, current_depth + 1, max_depth)?;
+        let entry_path = entry.path();
+        if entry_path.is_dir() || !is_markdown_file(entry_path, &cli.index) {
+            continue;
         }
-    } else if is_markdown_file(path) {
-        let src = fs::read_to_string(path).unwrap_or_else(|err| {
-            eprintln!("Failed to read {}: {}", path.display(), err);
-            std::process::exit(1);
+        // An input named on the command line is indexed whatever its name.
+        if entry.depth() > 0 && !filters.includes(entry_path) {
+            continue;
+        }
+        if let Some(id) = file_id(entry_path)
+            && !seen.insert(id)
+        {
+            continue;
+        }
+        files.push(entry_path.to_path_buf());
+    }
+
+    if cli.sort == SortOrder::Mtime {
+        // Stable, so files modified at the same time stay in path order.
+        files[first..].sort_by_cached_key(|file| {
+            fs::metadata(file)
+                .and_then(|metadata| metadata.modified())
+                .ok()
         });
+    }
+}
 
-        let sections = index_markdown(&src)?;
-        let file_path = path.to_string_lossy().to_string();
+/// The symlink and the directory it leads back to, if `err` is a loop.
+fn symlink_loop(err: &ignore::Error) -> Option<(&Path, &Path)> {
+    match err {
+        ignore::Error::Loop { ancestor, child } => Some((ancestor, child)),
+        ignore::Error::WithDepth { err, .. }
+        | ignore::Error::WithPath { err, .. }
+        | ignore::Error::WithLineNumber { err, .. } => symlink_loop(err),
+        _ => None,
+    }
+}
 
-        let file_docs: Vec<JsonDocumentElement> = sections
-            .into_iter()
-            .map(|s| JsonDocumentElement {
-                file_path: file_path.clone(),
-                header: s.title,
-                text_blocks: s.body_text,
-                code_blocks: s
-                    .code_blocks
-                    .into_iter()
-                    .map(|cb| JsonCodeBlock {
-                        value: cb.value,
-                        start_line: cb.start_line,
-                        end_line: cb.end_line,
-                    })
-                    .collect(),
-                start_line: s.start_line,
-                end_line: s.end_line,
-                heading_line: s.heading_line,
-            })
-            .collect();
+/// The title `--sort title` orders by: the frontmatter `title`, else the
+/// first heading, else the file name.
+fn document_title(file: &Path, document: &Document) -> String {
+    document
+        .metadata
+        .as_ref()
+        .and_then(|metadata| metadata.get("title"))
+        .and_then(|title| title.as_str())
+        .map(str::to_string)
+        .or_else(|| {
+            document
+                .sections
+                .iter()
+                .find(|section| section.heading_line.is_some())
+                .map(|section| section.title.clone())
+        })
+        .unwrap_or_else(|| {
+            file.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default()
+        })
+}
 
-        docs.extend(file_docs);
+/// A title, ordered naturally.
+#[derive(PartialEq, Eq)]
+struct TitleKey(String);
+
+impl Ord for TitleKey {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        sort::natural_cmp(&self.0, &other.0).then_with(|| self.0.cmp(&other.0))
+    }
+}
+
+impl PartialOrd for TitleKey {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
     }
+}
+
+/// Identity of a file, whichever path or link it was reached through.
+#[cfg(unix)]
+type FileId = (u64, u64);
+#[cfg(not(unix))]
+type FileId = PathBuf;
+
+/// The files found so far in a run.
+type SeenFiles = HashSet<FileId>;
+
+/// Identity of the file at `path`, whichever link it was reached through.
+#[cfg(unix)]
+fn file_id(path: &Path) -> Option<FileId> {
+    use std::os::unix::fs::MetadataExt;
 
-    Ok(())
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.dev(), metadata.ino()))
 }
 
-fn is_markdown_file(path: &Path) -> bool {
-    match path.extension().and_then(|e| e.to_str()) {
-        Some(ext) if ext.eq_ignore_ascii_case("md") || ext.eq_ignore_ascii_case("markdown") => true,
-        _ => false,
+/// Identity of the file at `path`, whichever link it was reached through.
+#[cfg(not(unix))]
+fn file_id(path: &Path) -> Option<FileId> {
+    fs::canonicalize(path).ok()
+}
+
+/// The `--include`/`--exclude` globs of a walk rooted at `root`, matched
+/// like `.gitignore` lines relative to the root.
+///
+/// Both only narrow the walk: excludes prune it alongside the ignore files,
+/// and includes are checked on the files that are left, so neither brings
+/// back ignored or hidden entries.
+struct PathFilters {
+    exclude: Override,
+    include: Gitignore,
+}
+
+impl PathFilters {
+    fn new(root: &Path, include: &[String], exclude: &[String]) -> Result<Self, ignore::Error> {
+        let mut excludes = OverrideBuilder::new(root);
+        for glob in exclude {
+            excludes.add(&format!("!{}", glob))?;
+        }
+
+        let mut includes = GitignoreBuilder::new(root);
+        for glob in include {
+            includes.add_line(None, glob)?;
+        }
+
+        Ok(PathFilters {
+            exclude: excludes.build()?,
+            include: includes.build()?,
+        })
+    }
+
+    /// Whether the file at `path` matches an include glob, or there are none.
+    fn includes(&self, path: &Path) -> bool {
+        self.include.is_empty()
+            || self
+                .include
+                .matched_path_or_any_parents(path, false)
+                .is_ignore()
     }
 }
 
+fn read_markdown(path: &Path) -> String {
+    fs::read_to_string(path).unwrap_or_else(|err| {
+        eprintln!("Failed to read {}: {}", path.display(), err);
+        std::process::exit(1);
+    })
+}
+
+fn to_json_element(
+    file_path: &str,
+    id: usize,
+    first_id: usize,
+    node: SectionNode,
+    metadata: Option<serde_json::Value>,
+    cli: &CliArgs,
+) -> JsonDocumentElement {
+    let s = node.section;
+
+    JsonDocumentElement {
+        id,
+        parent_id: node.parent.map(|parent| first_id + parent),
+        file_path: file_path.to_string(),
+        header: s.title,
+        level: s.level,
+        anchor: s.anchor,
+        breadcrumbs: node.breadcrumbs,
+        text_blocks: if cli.legacy_text_blocks {
+            JsonTextBlocks::plain(s.body_text)
+        } else {
+            JsonTextBlocks::structured(s.body_text)
+        },
+        code_blocks: s
+            .code_blocks
+            .into_iter()
+            .map(|cb| JsonCodeBlock {
+                kind: cb.kind,
+                lang: cb.lang,
+                meta: cb.meta,
+                attributes: cb.attributes,
+                value: cb.value,
+                start_line: cb.start_line,
+                end_line: cb.end_line,
+                start_column: cb.start_column,
+                end_column: cb.end_column,
+                start_offset: cb.start_offset,
+                end_offset: cb.end_offset,
+            })
+            .collect(),
+        inline_code: s
+            .inline_code
+            .into_iter()
+            .map(JsonInlineCode::from)
+            .collect(),
+        math_blocks: s.math_blocks.into_iter().map(JsonMath::from).collect(),
+        inline_math: s.inline_math.into_iter().map(JsonMath::from).collect(),
+        tables: s.tables.into_iter().map(JsonTable::from).collect(),
+        lists: s.lists.into_iter().map(JsonList::from).collect(),
+        links: s.links.into_iter().map(JsonLink::from).collect(),
+        images: s.images.into_iter().map(JsonImage::from).collect(),
+        callouts: s.callouts.into_iter().map(JsonCallout::from).collect(),
+        footnotes: s.footnotes.into_iter().map(JsonFootnote::from).collect(),
+        details: s.details.into_iter().map(JsonDetails::from).collect(),
+        raw_html: cli
+            .raw_html
+            .then(|| s.raw_html.into_iter().map(JsonRawHtml::from).collect()),
+        blocks: cli
+            .blocks
+            .then(|| s.blocks.into_iter().map(JsonBlock::from).collect()),
+        start_line: s.start_line,
+        end_line: s.end_line,
+        start_column: s.start_column,
+        end_column: s.end_column,
+        start_offset: s.start_offset,
+        end_offset: s.end_offset,
+        heading_line: s.heading_line,
+        metadata,
+    }
+}
+
+/// Whether `path` has a markdown extension: `.md` or `.markdown`, plus
+/// `.mdx` when MDX is enabled.
+fn is_markdown_file(path: &Path, options: &IndexOptions) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some(ext) if ext.eq_ignore_ascii_case("md")
+            || ext.eq_ignore_ascii_case("markdown")
+            || (options.mdx && ext.eq_ignore_ascii_case("mdx"))
+    )
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{parse_args, process_path};
+    use super::{
+        CliArgs, Command, SeenFiles, SortOrder, collect_markdown_files, parse_args, process_path,
+    };
+    use markdown2json::{CodeBlockKind, IndexOptions, JsonSectionNode, JsonTextBlocks, TextFormat};
     use std::fs;
     use std::path::PathBuf;
-    use std::time::{SystemTime, UNIX_EPOCH};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
     fn args(list: &[&str]) -> Vec<String> {
         list.iter().map(|s| s.to_string()).collect()
@@ -208,13 +693,150 @@ mod tests {
         ))
     }
 
+    fn unique_temp_dir() -> PathBuf {
+        let path = unique_temp_markdown_path().with_extension("d");
+        fs::create_dir_all(&path).expect("create temp dir");
+        path
+    }
+
     #[test]
     fn parses_multiple_inputs_with_depth() {
         let argv = args(&["program", "input1", "input2", "--depth", "3"]);
-        let (inputs, depth) = parse_args(&argv).expect("should parse");
+        let cli = parse_args(&argv).expect("should parse");
+
+        assert_eq!(cli.inputs, vec!["input1", "input2"]);
+        assert_eq!(cli.max_depth, Some(3));
+        assert!(!cli.tree);
+    }
+
+    #[test]
+    fn parses_tree_flag_in_any_flag_position() {
+        let argv = args(&["program", "input1", "--tree", "-d", "1"]);
+        let cli = parse_args(&argv).expect("should parse");
+
+        assert_eq!(cli.inputs, vec!["input1"]);
+        assert_eq!(cli.max_depth, Some(1));
+        assert!(cli.tree);
+    }
+
+    #[test]
+    fn parses_walk_filters() {
+        let argv = args(&[
+            "program",
+            "docs",
+            "--include",
+            "*.md",
+            "--exclude",
+            "vendor/**",
+            "--exclude",
+            "drafts",
+            "--hidden",
+            "--follow-symlinks",
+        ]);
+        let cli = parse_args(&argv).expect("should parse");
+        assert_eq!(cli.include, vec!["*.md"]);
+        assert_eq!(cli.exclude, vec!["vendor/**", "drafts"]);
+        assert!(cli.hidden);
+        assert!(cli.follow_symlinks);
+
+        let argv = args(&["program", "docs", "--exclude", "a/{b"]);
+        let err = parse_args(&argv).expect_err("unclosed alternation");
+        assert!(err.starts_with("Invalid glob pattern"), "{}", err);
+
+        let argv = args(&["program", "docs", "--include"]);
+        assert_eq!(
+            parse_args(&argv).expect_err("missing glob"),
+            "Expected a glob after --include"
+        );
+    }
+
+    #[test]
+    fn parses_sort_order() {
+        let argv = args(&["program", "docs", "--sort", "title"]);
+        let cli = parse_args(&argv).expect("should parse");
+        assert_eq!(cli.sort, SortOrder::Title);
+        assert_eq!(CliArgs::default().sort, SortOrder::Path);
+
+        let argv = args(&["program", "docs", "--sort", "size"]);
+        assert_eq!(
+            parse_args(&argv).expect_err("unknown order"),
+            "Invalid sort order: size (expected path, mtime or title)"
+        );
+    }
+
+    #[test]
+    fn parses_markdown_extension_flags() {
+        let argv = args(&[
+            "program",
+            "docs",
+            "--gfm",
+            "--math",
+            "--frontmatter",
+            "--strip-inline-code",
+            "--no-image-alt-text",
+        ]);
+        let cli = parse_args(&argv).expect("should parse");
 
-        assert_eq!(inputs, vec!["input1", "input2"]);
-        assert_eq!(depth, Some(3));
+        assert_eq!(
+            cli.index,
+            IndexOptions {
+                gfm: true,
+                mdx: false,
+                math: true,
+                frontmatter: true,
+                strip_inline_code: true,
+                image_alt_text: false,
+                ..IndexOptions::default()
+            }
+        );
+    }
+
+    #[test]
+    fn parses_text_format() {
+        let argv = args(&["program", "docs", "--text-format", "html"]);
+        let cli = parse_args(&argv).expect("should parse");
+        assert_eq!(cli.index.text_format, TextFormat::Html);
+
+        let argv = args(&["program", "docs", "--text-format", "rtf"]);
+        let err = parse_args(&argv).expect_err("should error");
+        assert!(err.contains("Invalid text format: rtf"));
+    }
+
+    #[test]
+    fn parses_check_links_subcommand() {
+        let argv = args(&["program", "check-links", "docs", "--gfm"]);
+        let cli = parse_args(&argv).expect("should parse");
+
+        assert_eq!(cli.command, Command::CheckLinks);
+        assert_eq!(cli.inputs, vec!["docs"]);
+        assert!(cli.index.gfm);
+
+        let argv = args(&["program", "check-links"]);
+        let err = parse_args(&argv).expect_err("should error");
+        assert!(err.starts_with("Usage:"));
+    }
+
+    #[test]
+    fn check_links_rejects_output_flags() {
+        for flag in ["--tree", "--blocks", "--raw-html", "--math-text"] {
+            let argv = args(&["program", "check-links", "docs", "--gfm", flag]);
+            let err = parse_args(&argv).expect_err("should error");
+            assert!(err.starts_with(&format!("{} only changes the JSON output", flag)));
+        }
+
+        let argv = args(&["program", "check-links", "docs", "--text-format", "html"]);
+        assert!(parse_args(&argv).is_err());
+
+        let argv = args(&["program", "docs", "--tree"]);
+        assert!(parse_args(&argv).expect("should parse").tree);
+    }
+
+    #[test]
+    fn errors_when_input_follows_flags() {
+        let argv = args(&["program", "input1", "--tree", "input2"]);
+        let err = parse_args(&argv).expect_err("should error");
+
+        assert!(err.contains("Unknown flag or flag placed before inputs: --tree"));
     }
 
     #[test]
@@ -233,6 +855,213 @@ mod tests {
         assert!(err.contains("Unknown flag or flag placed before inputs"));
     }
 
+    #[test]
+    fn directory_walk_honors_ignore_files_globs_and_hidden() {
+        let root = unique_temp_dir();
+        for file in [
+            "README.md",
+            "docs/guide.md",
+            "docs/draft.md",
+            "docs/notes.txt",
+            "docs/node_modules/pkg/README.md",
+            "docs/.cache/x.md",
+            ".secret.md",
+            "node_modules/pkg/README.md",
+            "target/doc.md",
+            "vendor/lib/README.md",
+            ".github/CONTRIBUTING.md",
+        ] {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().expect("parent")).expect("create dirs");
+            fs::write(&path, "# Title\n").expect("write file");
+        }
+        fs::write(root.join(".gitignore"), "node_modules/\n").expect("write .gitignore");
+        fs::write(root.join(".ignore"), "target\n").expect("write .ignore");
+        fs::write(root.join(".markdown2jsonignore"), "vendor/\n").expect("write ignore file");
+
+        let walk = |cli: &CliArgs| {
+            let mut files = Vec::new();
+            collect_markdown_files(&root, &mut files, &mut SeenFiles::new(), 0, cli);
+            let mut names: Vec<String> = files
+                .iter()
+                .map(|f| {
+                    let relative = f.strip_prefix(&root).expect("under root");
+                    relative.to_string_lossy().replace('\\', "/")
+                })
+                .collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(
+            walk(&CliArgs::default()),
+            vec!["README.md", "docs/draft.md", "docs/guide.md"]
+        );
+
+        let cli = CliArgs {
+            include: vec!["docs/**".to_string()],
+            exclude: vec!["draft.md".to_string()],
+            hidden: true,
+            ..CliArgs::default()
+        };
+        assert_eq!(walk(&cli), vec!["docs/.cache/x.md", "docs/guide.md"]);
+
+        // Includes only narrow the walk: ignored and hidden files under an
+        // included path stay out.
+        let cli = CliArgs {
+            include: vec!["docs/**".to_string(), "*.md".to_string()],
+            ..CliArgs::default()
+        };
+        assert_eq!(
+            walk(&cli),
+            vec!["README.md", "docs/draft.md", "docs/guide.md"]
+        );
+
+        let cli = CliArgs {
+            hidden: true,
+            ..CliArgs::default()
+        };
+        assert_eq!(
+            walk(&cli),
+            vec![
+                ".github/CONTRIBUTING.md",
+                ".secret.md",
+                "README.md",
+                "docs/.cache/x.md",
+                "docs/draft.md",
+                "docs/guide.md"
+            ]
+        );
+
+        fs::remove_dir_all(root).expect("cleanup temp dir");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_are_followed_on_request_without_loops_or_duplicates() {
+        use std::os::unix::fs::symlink;
+
+        let root = unique_temp_dir();
+        let outside = unique_temp_dir();
+        fs::create_dir_all(root.join("docs")).expect("create docs");
+        fs::write(root.join("docs/guide.md"), "# Guide\n").expect("write guide");
+        fs::write(outside.join("shared.md"), "# Shared\n").expect("write shared");
+        symlink(root.join("docs/guide.md"), root.join("README.md")).expect("file link");
+        symlink(root.join("docs"), root.join("alias")).expect("directory link");
+        symlink(&root, root.join("docs/loop")).expect("loop link");
+        symlink(&outside, root.join("shared")).expect("outside link");
+
+        let walk = |cli: &CliArgs| {
+            let mut files = Vec::new();
+            collect_markdown_files(&root, &mut files, &mut SeenFiles::new(), 0, cli);
+            let mut names: Vec<String> = files
+                .iter()
+                .map(|f| fs::read_to_string(f).expect("read file"))
+                .collect();
+            names.sort();
+            names
+        };
+
+        // The guide is reachable as docs/guide.md and README.md, but is
+        // listed once.
+        assert_eq!(walk(&CliArgs::default()), vec!["# Guide\n"]);
+
+        let cli = CliArgs {
+            follow_symlinks: true,
+            ..CliArgs::default()
+        };
+        assert_eq!(walk(&cli), vec!["# Guide\n", "# Shared\n"]);
+
+        // Dedup spans inputs: the guide named again, or through its link,
+        // is not listed a second time.
+        let mut seen = SeenFiles::new();
+        let mut files = Vec::new();
+        for input in ["docs", "docs/guide.md", "README.md"] {
+            collect_markdown_files(
+                &root.join(input),
+                &mut files,
+                &mut seen,
+                0,
+                &CliArgs::default(),
+            );
+        }
+        assert_eq!(files, vec![root.join("docs/guide.md")]);
+
+        fs::remove_dir_all(root).expect("cleanup temp dir");
+        fs::remove_dir_all(outside).expect("cleanup temp dir");
+    }
+
+    #[test]
+    fn files_are_emitted_in_the_requested_order() {
+        let root = unique_temp_dir();
+        let epoch = SystemTime::UNIX_EPOCH;
+        for (file, content, age) in [
+            ("ch10.md", "# Alpha\n", 3),
+            ("ch2.md", "# Zeta\n", 1),
+            ("ch1.md", "Intro.\n\n# Middle\n", 2),
+            ("b/ch1.md", "# Beta\n", 4),
+        ] {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().expect("parent")).expect("create dirs");
+            fs::write(&path, content).expect("write file");
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .and_then(|f| f.set_modified(epoch + Duration::from_secs(1000 - age)))
+                .expect("set mtime");
+        }
+
+        let headers = |sort: SortOrder| {
+            let cli = CliArgs {
+                sort,
+                ..CliArgs::default()
+            };
+            let mut docs = Vec::new();
+            process_path(&root, &mut docs, &mut SeenFiles::new(), 0, &cli);
+            docs.into_iter()
+                .map(|doc| doc.header)
+                .filter(|header| header != "(preamble)")
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            headers(SortOrder::Path),
+            vec!["Beta", "Middle", "Zeta", "Alpha"]
+        );
+        assert_eq!(
+            headers(SortOrder::Mtime),
+            vec!["Beta", "Alpha", "Middle", "Zeta"]
+        );
+        assert_eq!(
+            headers(SortOrder::Title),
+            vec!["Alpha", "Beta", "Middle", "Zeta"]
+        );
+
+        fs::remove_dir_all(root).expect("cleanup temp dir");
+    }
+
+    #[test]
+    fn mdx_files_are_indexed_with_mdx_and_parse_errors_skip_the_file() {
+        let root = unique_temp_dir();
+        fs::write(root.join("a.md"), "# Plain\n").expect("write md");
+        fs::write(root.join("b.mdx"), "# Component\n\n<Note>Hi</Note>\n").expect("write mdx");
+        fs::write(root.join("c.mdx"), "# Broken\n\nText {oops\n").expect("write mdx");
+
+        let headers = |cli: &CliArgs| {
+            let mut docs = Vec::new();
+            process_path(&root, &mut docs, &mut SeenFiles::new(), 0, cli);
+            docs.into_iter().map(|doc| doc.header).collect::<Vec<_>>()
+        };
+
+        assert_eq!(headers(&CliArgs::default()), vec!["Plain"]);
+
+        let mut cli = CliArgs::default();
+        cli.index.mdx = true;
+        assert_eq!(headers(&cli), vec!["Plain", "Component"]);
+
+        fs::remove_dir_all(root).expect("cleanup temp dir");
+    }
+
     #[test]
     fn process_path_emits_structured_code_blocks_with_line_metadata() {
         let path = unique_temp_markdown_path();
@@ -240,7 +1069,13 @@ mod tests {
         fs::write(&path, content).expect("write temp markdown");
 
         let mut docs = Vec::new();
-        process_path(&path, &mut docs, 0, None).expect("process markdown file");
+        process_path(
+            &path,
+            &mut docs,
+            &mut SeenFiles::new(),
+            0,
+            &CliArgs::default(),
+        );
 
         assert_eq!(docs.len(), 1);
         let doc = &docs[0];
@@ -249,10 +1084,222 @@ mod tests {
         assert_eq!(doc.start_line, Some(1));
         assert_eq!(doc.end_line, Some(5));
         assert_eq!(doc.heading_line, Some(1));
+        assert_eq!(doc.level, 1);
+        assert_eq!(doc.parent_id, None);
         assert_eq!(doc.code_blocks.len(), 1);
         assert_eq!(doc.code_blocks[0].value, "fn main() {}");
+        assert_eq!(doc.code_blocks[0].lang.as_deref(), Some("rust"));
+        assert_eq!(doc.code_blocks[0].kind, CodeBlockKind::Fenced);
         assert_eq!(doc.code_blocks[0].start_line, Some(3));
         assert_eq!(doc.code_blocks[0].end_line, Some(5));
+        assert_eq!(doc.code_blocks[0].start_offset, content.find("```"));
+        assert_eq!(doc.code_blocks[0].end_offset, Some(content.len() - 1));
+
+        fs::remove_file(path).expect("cleanup temp markdown");
+    }
+
+    #[test]
+    fn tree_output_nests_subsections() {
+        let path = unique_temp_markdown_path();
+        let content = "# Guide\n## Install\n### Linux\nUse apt.\n## Usage\n";
+        fs::write(&path, content).expect("write temp markdown");
+
+        let mut docs = Vec::new();
+        process_path(
+            &path,
+            &mut docs,
+            &mut SeenFiles::new(),
+            0,
+            &CliArgs::default(),
+        );
+        let trees = JsonSectionNode::nest(docs);
+
+        assert_eq!(trees.len(), 1);
+        let guide = &trees[0];
+        assert_eq!(guide.element.header, "Guide");
+        assert_eq!(guide.children.len(), 2);
+
+        let linux = &guide.children[0].children[0];
+        assert_eq!(linux.element.header, "Linux");
+        assert_eq!(linux.element.level, 3);
+        assert_eq!(linux.element.breadcrumbs, vec!["Guide", "Install"]);
+        let JsonTextBlocks::Structured(text_blocks) = &linux.element.text_blocks else {
+            panic!("text blocks should be structured by default");
+        };
+        assert_eq!(text_blocks.len(), 1);
+        assert_eq!(text_blocks[0].text, "Use apt.");
+
+        fs::remove_file(path).expect("cleanup temp markdown");
+    }
+
+    #[test]
+    fn text_blocks_carry_positions_unless_legacy_shape_is_requested() {
+        let path = unique_temp_markdown_path();
+        let content = "# Intro
+First.
+
+- a
+- b
+
+Third.
+";
+        fs::write(&path, content).expect("write temp markdown");
+
+        let mut docs = Vec::new();
+        process_path(
+            &path,
+            &mut docs,
+            &mut SeenFiles::new(),
+            0,
+            &CliArgs::default(),
+        );
+        let value = serde_json::to_value(&docs[0].text_blocks).expect("serialize");
+        assert_eq!(value[1]["text"], "a\nb");
+        assert_eq!(value[1]["kind"], "list");
+        assert_eq!(value[1]["start_line"], 4);
+        assert_eq!(value[2]["start_offset"], content.find("Third.").unwrap());
+
+        let cli = CliArgs {
+            legacy_text_blocks: true,
+            ..CliArgs::default()
+        };
+        let mut docs = Vec::new();
+        process_path(&path, &mut docs, &mut SeenFiles::new(), 0, &cli);
+        let value = serde_json::to_value(&docs[0].text_blocks).expect("serialize");
+        assert_eq!(value, serde_json::json!(["First.", "a\nb", "Third."]));
+
+        fs::remove_file(path).expect("cleanup temp markdown");
+    }
+
+    #[test]
+    fn process_path_links_records_to_enclosing_sections() {
+        let path = unique_temp_markdown_path();
+        fs::write(&path, "# Guide\n## Configuration\n# Other\n").expect("write temp markdown");
+
+        // Pretend another file was indexed first so ids are offset.
+        let mut docs = Vec::new();
+        process_path(
+            &path,
+            &mut docs,
+            &mut SeenFiles::new(),
+            0,
+            &CliArgs::default(),
+        );
+        process_path(
+            &path,
+            &mut docs,
+            &mut SeenFiles::new(),
+            0,
+            &CliArgs::default(),
+        );
+
+        assert_eq!(docs.len(), 6);
+        let config = &docs[4];
+        assert_eq!(config.id, 4);
+        assert_eq!(config.header, "Configuration");
+        assert_eq!(config.level, 2);
+        assert_eq!(config.parent_id, Some(3));
+        assert_eq!(config.breadcrumbs, vec!["Guide"]);
+        assert_eq!(docs[5].parent_id, None);
+
+        fs::remove_file(path).expect("cleanup temp markdown");
+    }
+
+    #[test]
+    fn process_path_attaches_frontmatter_to_every_record() {
+        let path = unique_temp_markdown_path();
+        fs::write(&path, "---\ntitle: Post\ndraft: true\n---\n# One\n# Two\n")
+            .expect("write temp markdown");
+
+        let cli = CliArgs {
+            index: IndexOptions {
+                frontmatter: true,
+                ..IndexOptions::default()
+            },
+            ..CliArgs::default()
+        };
+        let mut docs = Vec::new();
+        process_path(&path, &mut docs, &mut SeenFiles::new(), 0, &cli);
+
+        assert_eq!(docs.len(), 2);
+        for doc in &docs {
+            let metadata = doc.metadata.as_ref().expect("metadata");
+            assert_eq!(metadata["title"], "Post");
+            assert_eq!(metadata["draft"], true);
+        }
+
+        fs::remove_file(path).expect("cleanup temp markdown");
+    }
+
+    #[test]
+    fn process_path_emits_blocks_only_when_requested() {
+        let path = unique_temp_markdown_path();
+        fs::write(&path, "# Demo\nExplain.\n```sh\nrun\n```\nDone.\n")
+            .expect("write temp markdown");
+
+        let mut docs = Vec::new();
+        process_path(
+            &path,
+            &mut docs,
+            &mut SeenFiles::new(),
+            0,
+            &CliArgs::default(),
+        );
+        assert_eq!(docs[0].blocks, None);
+        let value = serde_json::to_value(&docs[0]).expect("serialize");
+        assert!(value.get("blocks").is_none());
+
+        let cli = CliArgs {
+            blocks: true,
+            ..CliArgs::default()
+        };
+        let mut docs = Vec::new();
+        process_path(&path, &mut docs, &mut SeenFiles::new(), 0, &cli);
+        let texts: Vec<&str> = docs[0]
+            .blocks
+            .as_ref()
+            .expect("blocks")
+            .iter()
+            .map(|b| b.text.as_str())
+            .collect();
+        assert_eq!(texts, vec!["Explain.", "run", "Done."]);
+
+        fs::remove_file(path).expect("cleanup temp markdown");
+    }
+
+    #[test]
+    fn process_path_emits_raw_html_only_when_requested() {
+        let path = unique_temp_markdown_path();
+        fs::write(
+            &path,
+            "# Demo
+
+<div align=\"center\">Hello</div>
+",
+        )
+        .expect("write temp markdown");
+
+        let mut docs = Vec::new();
+        process_path(
+            &path,
+            &mut docs,
+            &mut SeenFiles::new(),
+            0,
+            &CliArgs::default(),
+        );
+        assert_eq!(docs[0].raw_html, None);
+        let value = serde_json::to_value(&docs[0]).expect("serialize");
+        assert!(value.get("raw_html").is_none());
+
+        let cli = CliArgs {
+            raw_html: true,
+            ..CliArgs::default()
+        };
+        let mut docs = Vec::new();
+        process_path(&path, &mut docs, &mut SeenFiles::new(), 0, &cli);
+        let raw_html = docs[0].raw_html.as_ref().expect("raw_html");
+        assert_eq!(raw_html.len(), 1);
+        assert_eq!(raw_html[0].value, "<div align=\"center\">Hello</div>");
 
         fs::remove_file(path).expect("cleanup temp markdown");
     }
@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+/// GitHub-style anchor for a heading: lowercased, with punctuation dropped
+/// and spaces turned into hyphens.
+///
+/// This does not deduplicate; use a [`Slugger`] for all headings of a file.
+///
+/// ```
+/// assert_eq!(markdown2json::heading_slug("Install & Run!"), "install--run");
+/// ```
+pub fn heading_slug(title: &str) -> String {
+    title
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            '-' | '_' => Some(c),
+            c if c.is_alphanumeric() => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Generates unique anchors for the headings of one document, the way
+/// GitHub does: the second `## Usage` becomes `usage-1`, the third
+/// `usage-2`, and so on.
+#[derive(Debug, Default)]
+pub struct Slugger {
+    occurrences: HashMap<String, usize>,
+}
+
+impl Slugger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Unique anchor for `title`.
+    pub fn slug(&mut self, title: &str) -> String {
+        self.unique(heading_slug(title))
+    }
+
+    /// Record an explicit anchor (`{#custom-id}`) so generated anchors do
+    /// not collide with it. The anchor itself is kept as written.
+    pub fn reserve(&mut self, anchor: &str) {
+        self.occurrences.entry(anchor.to_string()).or_insert(0);
+    }
+
+    fn unique(&mut self, original: String) -> String {
+        let mut result = original.clone();
+        while self.occurrences.contains_key(&result) {
+            let count = self
+                .occurrences
+                .get_mut(&original)
+                .expect("original slug is recorded before any suffixed one");
+            *count += 1;
+            result = format!("{}-{}", original, count);
+        }
+        self.occurrences.insert(result.clone(), 0);
+        result
+    }
+}
+
+/// Split a trailing `{#custom-id}` heading attribute off `title`.
+pub(crate) fn split_custom_id(title: &str) -> (String, Option<String>) {
+    let trimmed = title.trim_end();
+    if let Some(open) = trimmed.rfind("{#")
+        && trimmed.ends_with('}')
+    {
+        let id = &trimmed[open + 2..trimmed.len() - 1];
+        if !id.is_empty() && !id.contains(|c: char| c.is_whitespace() || c == '}') {
+            return (trimmed[..open].trim_end().to_string(), Some(id.to_string()));
+        }
+    }
+    (title.to_string(), None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Slugger, heading_slug, split_custom_id};
+
+    #[test]
+    fn slugs_drop_punctuation_and_hyphenate_spaces() {
+        assert_eq!(heading_slug("Getting Started"), "getting-started");
+        assert_eq!(heading_slug("What's new in v1.2?"), "whats-new-in-v12");
+        assert_eq!(
+            heading_slug("snake_case and kebab-case"),
+            "snake_case-and-kebab-case"
+        );
+        assert_eq!(heading_slug("Überblick"), "überblick");
+    }
+
+    #[test]
+    fn duplicate_headings_get_numeric_suffixes() {
+        let mut slugger = Slugger::new();
+
+        assert_eq!(slugger.slug("Usage"), "usage");
+        assert_eq!(slugger.slug("Usage"), "usage-1");
+        assert_eq!(slugger.slug("Usage"), "usage-2");
+        // A heading that happens to look like a generated suffix.
+        assert_eq!(slugger.slug("Usage 3"), "usage-3");
+        assert_eq!(slugger.slug("Usage"), "usage-4");
+    }
+
+    #[test]
+    fn reserved_anchors_are_not_reused() {
+        let mut slugger = Slugger::new();
+        slugger.reserve("setup");
+
+        assert_eq!(slugger.slug("Setup"), "setup-1");
+    }
+
+    #[test]
+    fn custom_ids_are_split_from_titles() {
+        assert_eq!(
+            split_custom_id("Install {#setup}"),
+            ("Install".to_string(), Some("setup".to_string()))
+        );
+        assert_eq!(
+            split_custom_id("Use {braces}"),
+            ("Use {braces}".to_string(), None)
+        );
+        assert_eq!(
+            split_custom_id("Empty {#}"),
+            ("Empty {#}".to_string(), None)
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use std::{cmp::Ordering, ffi::OsStr, iter::Peekable, str::FromStr};
+
+/// The order files are emitted in, within each input.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Natural path order: directory by directory, `ch2.md` before `ch10.md`.
+    #[default]
+    Path,
+    /// Least recently modified first.
+    Mtime,
+    /// By document title: the frontmatter `title`, else the first heading,
+    /// else the file name.
+    Title,
+}
+
+impl FromStr for SortOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "path" => Ok(SortOrder::Path),
+            "mtime" => Ok(SortOrder::Mtime),
+            "title" => Ok(SortOrder::Title),
+            _ => Err(format!(
+                "Invalid sort order: {} (expected path, mtime or title)",
+                s
+            )),
+        }
+    }
+}
+
+/// Compare file names so that runs of digits compare by value and letters
+/// ignore case. Names that only differ there are ordered bytewise, so the
+/// order is total.
+pub fn compare_names(a: &OsStr, b: &OsStr) -> Ordering {
+    natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()).then_with(|| a.cmp(b))
+}
+
+/// Compare strings the way people expect lists to be sorted: `Intro` before
+/// `intro 2` before `intro 10`.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+
+    loop {
+        let ordering = match (a.peek(), b.peek()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let x = take_number(&mut a);
+                let y = take_number(&mut b);
+                // Longer numbers without leading zeros are larger, which
+                // avoids parsing numbers of any length.
+                x.len().cmp(&y.len()).then_with(|| x.cmp(&y))
+            }
+            (Some(x), Some(y)) => {
+                let ordering = x.to_lowercase().cmp(y.to_lowercase());
+                a.next();
+                b.next();
+                ordering
+            }
+        };
+
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+/// Consume a run of digits, without its leading zeros.
+fn take_number(chars: &mut Peekable<impl Iterator<Item = char>>) -> String {
+    let mut digits = String::new();
+    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+        if !(digits.is_empty() && c == '0') {
+            digits.push(c);
+        }
+    }
+    digits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_sort_naturally_and_totally() {
+        let mut names = vec![
+            "ch10.md",
+            "ch2.md",
+            "Ch1.md",
+            "ch1.md",
+            "ch01.md",
+            "appendix.md",
+            "ch2-b.md",
+        ];
+        names.sort_by(|a, b| compare_names(OsStr::new(a), OsStr::new(b)));
+        assert_eq!(
+            names,
+            vec![
+                "appendix.md",
+                "Ch1.md",
+                "ch01.md",
+                "ch1.md",
+                "ch2-b.md",
+                "ch2.md",
+                "ch10.md"
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_sort_order() {
+        assert_eq!("mtime".parse(), Ok(SortOrder::Mtime));
+        assert!("size".parse::<SortOrder>().is_err());
+    }
+}
@@ -0,0 +1,357 @@
+use crate::links::Definitions;
+use crate::{IndexOptions, node_end_offset, node_start_line, node_start_offset};
+use crate::{callouts, html};
+use markdown::{CompileOptions, Options, mdast};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// How section text blocks are emitted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextFormat {
+    /// Formatting dropped, see [`IndexOptions`] for what is kept.
+    #[default]
+    Plain,
+    /// The block's exact markdown source.
+    Markdown,
+    /// The block compiled to HTML. Raw HTML in the source is escaped and
+    /// dangerous URLs (`javascript:` and the like) are dropped.
+    Html,
+}
+
+impl FromStr for TextFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plain" => Ok(TextFormat::Plain),
+            "markdown" => Ok(TextFormat::Markdown),
+            "html" => Ok(TextFormat::Html),
+            _ => Err(format!(
+                "Invalid text format: {} (expected plain, markdown or html)",
+                s
+            )),
+        }
+    }
+}
+
+/// Turns top-level nodes into text blocks in the selected [`TextFormat`],
+/// slicing the source by node positions.
+pub(crate) struct TextFormatter<'a> {
+    src: &'a str,
+    options: Options,
+    format: TextFormat,
+    /// Link definitions in markdown syntax, put in front of every HTML slice
+    /// so reference links still resolve.
+    definitions: String,
+}
+
+impl<'a> TextFormatter<'a> {
+    pub(crate) fn new(src: &'a str, options: &IndexOptions, definitions: &Definitions) -> Self {
+        let definitions = match options.text_format {
+            TextFormat::Html => definitions
+                .iter()
+                .map(|(identifier, (url, title))| definition_source(identifier, url, title))
+                .collect(),
+            TextFormat::Plain | TextFormat::Markdown => String::new(),
+        };
+
+        TextFormatter {
+            src,
+            options: Options {
+                parse: options.parse_options(),
+                compile: CompileOptions::default(),
+            },
+            format: options.text_format,
+            definitions,
+        }
+    }
+
+    /// The text of `node` in the selected format, given its plain text.
+    /// Falls back to the plain text if the node has no position.
+    pub(crate) fn format(&self, node: &mdast::Node, plain: String) -> String {
+        let source = node_start_offset(node)
+            .zip(node_end_offset(node))
+            .and_then(|(start, end)| self.src.get(start..end));
+
+        // Lists can end with the blank line that follows them.
+        match (self.format, source.map(str::trim_end)) {
+            (TextFormat::Markdown, Some(source)) => source.to_string(),
+            (TextFormat::Html, Some(source)) => {
+                let value = format!("{}\n{}", self.definitions, source);
+                markdown::to_html_with_options(&value, &self.options)
+                    .map(|html| html.trim_end().to_string())
+                    .unwrap_or(plain)
+            }
+            _ => plain,
+        }
+    }
+}
+
+/// `[identifier]: <url> "title"`, escaped so it parses back to the same
+/// definition.
+fn definition_source(identifier: &str, url: &str, title: &Option<String>) -> String {
+    let escape = |s: &str, special: &[char]| {
+        s.chars().fold(String::new(), |mut out, c| {
+            if c == '\\' || special.contains(&c) {
+                out.push('\\');
+            }
+            out.push(c);
+            out
+        })
+    };
+
+    let mut line = format!("[{}]: <{}>", identifier, escape(url, &['<', '>']));
+    if let Some(title) = title {
+        line.push_str(&format!(" \"{}\"", escape(title, &['"'])));
+    }
+    line.push('\n');
+    line
+}
+
+/// Renders nodes to plain text that reads like the rendered page: blocks go
+/// on their own lines, hard breaks become newlines, table cells are separated
+/// by tabs, images are replaced by their alt text and formatting is dropped.
+pub(crate) struct PlainTextRenderer<'a> {
+    options: &'a IndexOptions,
+    definitions: &'a Definitions,
+    /// Source lines that open or close a directive container.
+    directive_lines: &'a [usize],
+}
+
+impl<'a> PlainTextRenderer<'a> {
+    pub(crate) fn new(options: &'a IndexOptions, definitions: &'a Definitions) -> Self {
+        PlainTextRenderer {
+            options,
+            definitions,
+            directive_lines: &[],
+        }
+    }
+
+    /// The same renderer, dropping the directive markers on `lines`.
+    pub(crate) fn with_directive_lines<'b>(&self, lines: &'b [usize]) -> PlainTextRenderer<'b>
+    where
+        'a: 'b,
+    {
+        PlainTextRenderer {
+            options: self.options,
+            definitions: self.definitions,
+            directive_lines: lines,
+        }
+    }
+
+    pub(crate) fn render(&self, node: &mdast::Node) -> String {
+        let mut out = String::new();
+        self.render_into(node, &mut out);
+        out
+    }
+
+    fn render_into(&self, node: &mdast::Node, out: &mut String) {
+        match node {
+            mdast::Node::Text(text) => out.push_str(&text.value),
+            mdast::Node::InlineCode(code) => {
+                if !self.options.strip_inline_code {
+                    out.push_str(&code.value);
+                }
+            }
+            mdast::Node::Break(_) => out.push('\n'),
+            mdast::Node::Math(mdast::Math { value, .. })
+            | mdast::Node::InlineMath(mdast::InlineMath { value, .. }) => {
+                if self.options.math_text {
+                    out.push_str(value);
+                }
+            }
+
+            mdast::Node::Link(link) => {
+                self.render_children(node, out);
+                self.push_url(&link.url, out);
+            }
+            mdast::Node::LinkReference(reference) => {
+                self.render_children(node, out);
+                if let Some((url, _)) = self.definitions.get(&reference.identifier) {
+                    self.push_url(url, out);
+                }
+            }
+            mdast::Node::Image(mdast::Image { alt, .. })
+            | mdast::Node::ImageReference(mdast::ImageReference { alt, .. }) => {
+                if self.options.image_alt_text {
+                    out.push_str(alt);
+                }
+            }
+            // Tags are dropped, the text inside them is kept.
+            mdast::Node::Html(node) => out.push_str(&html::html_to_text(&node.value)),
+            mdast::Node::FootnoteReference(reference) => {
+                if self.options.footnote_markers {
+                    let label = reference.label.as_ref().unwrap_or(&reference.identifier);
+                    out.push_str(&format!("[^{}]", label));
+                }
+            }
+
+            mdast::Node::Paragraph(_) => {
+                let mut text = String::new();
+                self.render_children(node, &mut text);
+                let first_line = node_start_line(node).unwrap_or_default();
+                out.push_str(&callouts::strip_directive_markers(
+                    text,
+                    first_line,
+                    self.directive_lines,
+                ));
+            }
+            mdast::Node::Blockquote(_) => {
+                let mut text = String::new();
+                self.render_lines(node, &mut text);
+                if callouts::alert_name(node).is_some() {
+                    // The first line is the `[!NAME]` marker.
+                    text = text
+                        .split_once('\n')
+                        .map(|(_, rest)| rest)
+                        .unwrap_or_default()
+                        .to_string();
+                }
+                out.push_str(&text);
+            }
+
+            // Containers of blocks: one block per line.
+            mdast::Node::Root(_)
+            | mdast::Node::FootnoteDefinition(_)
+            | mdast::Node::List(_)
+            | mdast::Node::ListItem(_)
+            | mdast::Node::MdxJsxFlowElement(_)
+            | mdast::Node::Table(_) => self.render_lines(node, out),
+            mdast::Node::TableRow(row) => {
+                for (i, cell) in row.children.iter().enumerate() {
+                    if i > 0 {
+                        out.push('\t');
+                    }
+                    self.render_into(cell, out);
+                }
+            }
+
+            // Code is indexed as code blocks, and the rest has no text.
+            mdast::Node::Code(_)
+            | mdast::Node::Definition(_)
+            | mdast::Node::ThematicBreak(_)
+            | mdast::Node::Yaml(_)
+            | mdast::Node::Toml(_)
+            | mdast::Node::MdxjsEsm(_)
+            | mdast::Node::MdxFlowExpression(_)
+            | mdast::Node::MdxTextExpression(_) => {}
+
+            // Headings, table cells and inline formatting.
+            _ => self.render_children(node, out),
+        }
+    }
+
+    /// Render inline children. Where a child renders to nothing, like
+    /// stripped code or math, the spaces around it collapse into one.
+    fn render_children(&self, node: &mdast::Node, out: &mut String) {
+        let Some(children) = node.children() else {
+            return;
+        };
+
+        let mut removed = false;
+        for child in children {
+            let start = out.len();
+            self.render_into(child, out);
+            if out.len() == start {
+                removed = true;
+            } else if removed {
+                if out[..start].is_empty() || out[..start].ends_with(char::is_whitespace) {
+                    let added = out[start..].trim_start().len();
+                    out.replace_range(start..out.len() - added, "");
+                }
+                removed = false;
+            }
+        }
+        if removed {
+            out.truncate(out.trim_end().len());
+        }
+    }
+
+    /// Render each child block, skipping empty ones, one per line.
+    fn render_lines(&self, node: &mdast::Node, out: &mut String) {
+        let Some(children) = node.children() else {
+            return;
+        };
+
+        let mut first = true;
+        for child in children {
+            let text = self.render(child);
+            if text.trim().is_empty() {
+                continue;
+            }
+            if !first {
+                out.push('\n');
+            }
+            out.push_str(&text);
+            first = false;
+        }
+    }
+
+    fn push_url(&self, url: &str, out: &mut String) {
+        if self.options.link_urls && !out.ends_with(url) {
+            out.push_str(&format!(" ({})", url));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{IndexOptions, index_markdown_with_options};
+
+    fn texts(src: &str, options: &IndexOptions) -> Vec<String> {
+        let sections = index_markdown_with_options(src, options).expect("parse ok");
+        sections[0]
+            .body_text
+            .iter()
+            .map(|b| b.text.clone())
+            .collect()
+    }
+
+    #[test]
+    fn breaks_blocks_and_table_cells_are_separated() {
+        let src = "Line one\\\nline two\n\n> First.\n>\n> - a\n> - b\n\n| a | b |\n| - | - |\n| 1 | 2 |\n";
+        let options = IndexOptions {
+            gfm: true,
+            ..IndexOptions::default()
+        };
+
+        assert_eq!(
+            texts(src, &options),
+            vec!["Line one\nline two", "First.\na\nb", "a\tb\n1\t2"]
+        );
+    }
+
+    #[test]
+    fn link_urls_image_alt_text_and_footnotes_are_optional() {
+        let src = "See [docs](https://example.com), <https://example.org>, [ref][r], ![chart](c.png) and this[^1].\n\n[r]: ref.md\n[^1]: Note.\n";
+        let gfm = IndexOptions {
+            gfm: true,
+            ..IndexOptions::default()
+        };
+
+        assert_eq!(
+            texts(src, &gfm)[0],
+            "See docs, https://example.org, ref, chart and this."
+        );
+
+        let options = IndexOptions {
+            link_urls: true,
+            footnote_markers: true,
+            ..gfm
+        };
+        assert_eq!(
+            texts(src, &options)[0],
+            "See docs (https://example.com), https://example.org, ref (ref.md), chart and this[^1]."
+        );
+
+        let options = IndexOptions {
+            image_alt_text: false,
+            ..gfm
+        };
+        assert_eq!(
+            texts(src, &options)[0],
+            "See docs, https://example.org, ref, and this."
+        );
+    }
+}
@@ -0,0 +1,196 @@
+use crate::{CodeBlock, Section, index_markdown};
+use markdown::message::Message;
+
+/// A section placed in the heading hierarchy of its document.
+///
+/// `parent` and `children` are indices into [`SectionTree::nodes`].
+#[derive(Debug, Clone)]
+pub struct SectionNode {
+    pub section: Section,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+    /// Titles of all ancestor sections, outermost first.
+    pub breadcrumbs: Vec<String>,
+}
+
+/// Sections of one document arranged as a tree.
+///
+/// Nodes are stored in document order, so a node's subtree is always a
+/// contiguous run of `nodes` starting right after it.
+#[derive(Debug, Clone, Default)]
+pub struct SectionTree {
+    pub nodes: Vec<SectionNode>,
+    pub roots: Vec<usize>,
+}
+
+impl SectionTree {
+    /// Build a tree from the flat sections returned by [`index_markdown`].
+    ///
+    /// A section becomes a child of the closest preceding section with a
+    /// lower heading level. Skipped levels (`#` followed by `###`) still nest
+    /// under the nearest shallower heading. The synthetic preamble (level 0)
+    /// is always a root and never gets children.
+    pub fn from_sections(sections: Vec<Section>) -> Self {
+        let mut tree = SectionTree::default();
+        // Indices of the currently open ancestors, outermost first.
+        let mut stack: Vec<usize> = Vec::new();
+
+        for section in sections {
+            let index = tree.nodes.len();
+
+            if section.level == 0 {
+                stack.clear();
+                tree.roots.push(index);
+                tree.nodes.push(SectionNode {
+                    section,
+                    parent: None,
+                    children: Vec::new(),
+                    breadcrumbs: Vec::new(),
+                });
+                continue;
+            }
+
+            while let Some(&top) = stack.last() {
+                if tree.nodes[top].section.level >= section.level {
+                    stack.pop();
+                } else {
+                    break;
+                }
+            }
+
+            let parent = stack.last().copied();
+            let breadcrumbs = stack
+                .iter()
+                .map(|&i| tree.nodes[i].section.title.clone())
+                .collect();
+
+            match parent {
+                Some(p) => tree.nodes[p].children.push(index),
+                None => tree.roots.push(index),
+            }
+
+            tree.nodes.push(SectionNode {
+                section,
+                parent,
+                children: Vec::new(),
+                breadcrumbs,
+            });
+            stack.push(index);
+        }
+
+        tree
+    }
+
+    /// Indices of all descendants of `index`, in document order.
+    pub fn descendants(&self, index: usize) -> Vec<usize> {
+        let mut out = Vec::new();
+        self.collect_descendants(index, &mut out);
+        out
+    }
+
+    fn collect_descendants(&self, index: usize, out: &mut Vec<usize>) {
+        for &child in &self.nodes[index].children {
+            out.push(child);
+            self.collect_descendants(child, out);
+        }
+    }
+
+    /// Body text of the section at `index` followed by that of all its
+    /// descendants, in document order.
+    pub fn rolled_up_text(&self, index: usize) -> Vec<String> {
+        std::iter::once(index)
+            .chain(self.descendants(index))
+            .flat_map(|i| self.nodes[i].section.body_text.iter())
+            .map(|block| block.text.clone())
+            .collect()
+    }
+
+    /// Code blocks of the section at `index` followed by those of all its
+    /// descendants, in document order.
+    pub fn rolled_up_code_blocks(&self, index: usize) -> Vec<&CodeBlock> {
+        std::iter::once(index)
+            .chain(self.descendants(index))
+            .flat_map(|i| self.nodes[i].section.code_blocks.iter())
+            .collect()
+    }
+
+    /// Last line covered by the section at `index` including its descendants.
+    pub fn subtree_end_line(&self, index: usize) -> Option<usize> {
+        std::iter::once(index)
+            .chain(self.descendants(index))
+            .filter_map(|i| self.nodes[i].section.end_line)
+            .max()
+    }
+}
+
+/// Parse a markdown document and arrange its sections as a heading tree.
+pub fn index_markdown_tree(src: &str) -> Result<SectionTree, Message> {
+    Ok(SectionTree::from_sections(index_markdown(src)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GUIDE: &str = "Intro text.\n# Guide\nGuide text.\n## Install\n### Linux\nUse apt.\n### macOS\nUse brew.\n## Usage\nRun it.\n# FAQ\n";
+
+    #[test]
+    fn headings_nest_by_level() {
+        let tree = index_markdown_tree(GUIDE).expect("parse ok");
+
+        let titles: Vec<&str> = tree
+            .nodes
+            .iter()
+            .map(|n| n.section.title.as_str())
+            .collect();
+        assert_eq!(
+            titles,
+            vec![
+                "(preamble)",
+                "Guide",
+                "Install",
+                "Linux",
+                "macOS",
+                "Usage",
+                "FAQ"
+            ]
+        );
+
+        assert_eq!(tree.roots, vec![0, 1, 6]);
+        assert_eq!(tree.nodes[1].children, vec![2, 5]);
+        assert_eq!(tree.nodes[2].children, vec![3, 4]);
+        assert_eq!(tree.nodes[3].parent, Some(2));
+        assert_eq!(tree.nodes[0].parent, None);
+        assert!(tree.nodes[0].children.is_empty());
+    }
+
+    #[test]
+    fn breadcrumbs_list_ancestor_titles() {
+        let tree = index_markdown_tree(GUIDE).expect("parse ok");
+
+        assert_eq!(tree.nodes[3].breadcrumbs, vec!["Guide", "Install"]);
+        assert_eq!(tree.nodes[5].breadcrumbs, vec!["Guide"]);
+        assert!(tree.nodes[6].breadcrumbs.is_empty());
+    }
+
+    #[test]
+    fn skipped_levels_nest_under_nearest_shallower_heading() {
+        let tree = index_markdown_tree("# A\n### C\n## B\n").expect("parse ok");
+
+        assert_eq!(tree.nodes[0].children, vec![1, 2]);
+        assert_eq!(tree.nodes[1].parent, Some(0));
+        assert_eq!(tree.nodes[2].parent, Some(0));
+    }
+
+    #[test]
+    fn child_content_rolls_up_into_parent() {
+        let tree = index_markdown_tree(GUIDE).expect("parse ok");
+
+        assert_eq!(
+            tree.rolled_up_text(2),
+            vec!["Use apt.".to_string(), "Use brew.".to_string()]
+        );
+        assert_eq!(tree.rolled_up_text(1).len(), 4);
+        assert_eq!(tree.subtree_end_line(1), Some(10));
+    }
+}